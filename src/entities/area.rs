@@ -0,0 +1,87 @@
+use macroquad::prelude::*;
+
+// Cell of a body closed by three springs, whose area is kept close to the rest one
+#[derive(Copy, Clone, Debug)]
+pub struct Triangle {
+    pub indices: [usize; 3],
+    // Signed, so that a triangle turned inside out is pushed back
+    rest_area: f32,
+}
+
+impl Triangle {
+    pub fn new(positions: &[Vec2], indices: [usize; 3]) -> Triangle {
+        let mut triangle = Triangle {
+            indices,
+            rest_area: 0.,
+        };
+        triangle.rest_area = triangle.get_area(positions);
+        triangle
+    }
+
+    // Same triangle over renumbered dots
+    pub fn with_indices(&self, indices: [usize; 3]) -> Triangle {
+        Triangle { indices, ..*self }
+    }
+
+    pub fn has_side(&self, index_1: usize, index_2: usize) -> bool {
+        self.indices.contains(&index_1) && self.indices.contains(&index_2)
+    }
+
+    pub fn get_area(&self, positions: &[Vec2]) -> f32 {
+        let [a, b, c] = self.indices.map(|index| positions[index]);
+        (b - a).perp_dot(c - a) / 2.
+    }
+
+    // Gradient of the area with respect to the position of every corner
+    fn get_area_gradients(&self, positions: &[Vec2]) -> [Vec2; 3] {
+        let [a, b, c] = self.indices.map(|index| positions[index]);
+        [(c - b).perp(), (a - c).perp(), (b - a).perp()].map(|side| side / 2.)
+    }
+
+    // Forces on the corners, proportional to the relative change of area and the bulk modulus
+    pub fn add_forces(&self, positions: &[Vec2], bulk_modulus: f32, forces: &mut [Vec2]) {
+        let strain = (self.get_area(positions) - self.rest_area) / self.rest_area.abs();
+        self.indices
+            .iter()
+            .zip(self.get_area_gradients(positions))
+            .for_each(|(&index, gradient)| forces[index] -= gradient * strain * bulk_modulus);
+    }
+
+    pub fn get_potential_energy(&self, positions: &[Vec2], bulk_modulus: f32) -> f32 {
+        bulk_modulus * (self.get_area(positions) - self.rest_area).powi(2)
+            / (2. * self.rest_area.abs())
+    }
+
+    // Moves the corners towards the rest area as an XPBD constraint, with the compliance
+    // given by the bulk modulus, accumulating the Lagrange multiplier of the constraint
+    pub fn solve_area_constraint(
+        &self,
+        positions: &mut [Vec2],
+        inverse_masses: &[f32],
+        lambda: &mut f32,
+        bulk_modulus: f32,
+        delta_t: f32,
+    ) {
+        let alpha = self.rest_area.abs() / (bulk_modulus * delta_t * delta_t);
+        let gradients = self.get_area_gradients(positions);
+        let weight = self
+            .indices
+            .iter()
+            .zip(gradients)
+            .map(|(&index, gradient)| inverse_masses[index] * gradient.length_squared())
+            .sum::<f32>();
+        if weight + alpha == 0. {
+            return;
+        }
+
+        let constraint = self.get_area(positions) - self.rest_area;
+        let delta_lambda = (-constraint - alpha * *lambda) / (weight + alpha);
+        self.indices
+            .iter()
+            .zip(gradients)
+            .for_each(|(&index, gradient)| {
+                positions[index] += gradient * delta_lambda * inverse_masses[index]
+            });
+        *lambda += delta_lambda;
+    }
+}
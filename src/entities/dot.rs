@@ -1,8 +1,12 @@
+use super::interaction::Surface;
 use super::polygon::*;
+use super::segment::*;
 use macroquad::prelude::*;
 
 pub const DELTA_T_RUNGE_KUTTA: f32 = 0.25;
 pub const RADIUS: f32 = 10.;
+// Lightest mass of a Dot, keeping its inverse mass finite
+pub const MIN_MASS: f32 = 0.001;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Dot {
@@ -11,43 +15,91 @@ pub struct Dot {
     pub radius: f32,
     prev_pos: Vec2,
     pub acceleration: Vec2,
-    freeze: bool,
+    // Position in the rest shape of the body
+    pub rest_pos: Vec2,
+    // Zero for a Dot nothing can move
+    inverse_mass: f32,
+    // Position the Dot is held at, if pinned
+    pin: Option<Vec2>,
 }
 
 impl Dot {
-    pub fn new(pos: Option<Vec2>) -> Dot {
-        let initial_position =
-            pos.unwrap_or_else(|| vec2(screen_width() / 2., screen_height() / 2.));
+    pub fn new(pos: Vec2) -> Dot {
         Dot {
-            pos: initial_position,
-            prev_pos: initial_position,
+            pos,
+            prev_pos: pos,
             radius: RADIUS,
             vel: vec2(0., 0.),
             acceleration: vec2(0., 0.),
-            freeze: false,
+            rest_pos: pos,
+            inverse_mass: 1.,
+            pin: None,
         }
     }
-    pub fn is_out_of_bounds(&self) -> bool {
-        (self.pos[0]).abs() > screen_width() * 2. || (self.pos[1]).abs() > screen_height() * 2.
+    pub fn is_out_of_bounds(&self, bounds: &BoundingBox) -> bool {
+        self.pos[0] < bounds.min_hor
+            || self.pos[0] > bounds.max_hor
+            || self.pos[1] < bounds.min_ver
+            || self.pos[1] > bounds.max_ver
     }
 
-    pub fn update(&mut self) {
+    pub fn update(&mut self, pos: Vec2, vel: Vec2, max_speed: f32) {
         self.prev_pos = self.pos;
-        if !self.freeze {
-            self.vel += self.acceleration * DELTA_T_RUNGE_KUTTA;
-            self.vel = if self.vel.length() < 100. {
-                self.vel
-            } else {
-                self.vel.normalize() * 10.
-            };
-            self.pos += self.vel * DELTA_T_RUNGE_KUTTA;
+        if self.is_pinned() {
+            // Carried towards its target at the velocity given by follow_pin
+            self.pos = pos;
+        } else if !self.is_frozen() {
+            self.vel = vel.clamp_length_max(max_speed);
+            self.pos = pos;
         }
         self.acceleration = vec2(0., 0.);
     }
 
-    pub fn update_runge(&mut self, push_vec: &Vec2) {
-        self.pos += *push_vec;
-        self.vel = (self.pos - self.prev_pos) / DELTA_T_RUNGE_KUTTA;
+    pub fn is_frozen(&self) -> bool {
+        self.inverse_mass() == 0.
+    }
+
+    // Own mass of the Dot, kept while it is pinned
+    pub fn mass(&self) -> f32 {
+        1. / self.inverse_mass
+    }
+
+    pub fn inverse_mass(&self) -> f32 {
+        if self.is_pinned() {
+            0.
+        } else {
+            self.inverse_mass
+        }
+    }
+
+    // An infinite mass makes the Dot immovable, while a mass that is not positive
+    // is raised to the lightest one rather than freezing the Dot
+    pub fn set_mass(&mut self, mass: f32) {
+        self.inverse_mass = 1. / mass.max(MIN_MASS);
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pin.is_some()
+    }
+
+    pub fn pin_target(&self) -> Option<Vec2> {
+        self.pin
+    }
+
+    // Holds the Dot still, or makes it follow the target, which can be moved by pinning again
+    pub fn pin(&mut self, target: Option<Vec2>) {
+        self.pin = Some(target.unwrap_or(self.pos));
+    }
+
+    pub fn unpin(&mut self) {
+        self.pin = None;
+    }
+
+    // Gives a pinned Dot the velocity taking it to its target over the step
+    pub fn follow_pin(&mut self, delta_t: f32) {
+        if let Some(target) = self.pin {
+            self.vel = (target - self.pos) / delta_t;
+        }
     }
 
     pub fn add_acceleration(&mut self, acceleration: Vec2) {
@@ -58,26 +110,59 @@ impl Dot {
         self.acceleration += vec2(0., 9.8);
     }
 
-    pub fn push(&mut self, push_vec: &Vec2) {
-        if self.freeze {
+    pub fn add_velocity(&mut self, velocity: &Vec2) {
+        if self.is_frozen() {
+            return;
+        }
+
+        self.vel += *velocity;
+    }
+
+    // Distance moved over the last update and the contacts since
+    pub fn displacement(&self) -> Vec2 {
+        self.pos - self.prev_pos
+    }
+
+    // Moves Dot out of a contact without changing its velocity
+    pub fn shift(&mut self, offset: &Vec2) {
+        if self.is_frozen() {
+            return;
+        }
+
+        self.pos += *offset;
+    }
+
+    // Moves Dot out of a contact, bouncing and rubbing against the surface
+    pub fn resolve_contact(&mut self, push_vec: &Vec2, surface: &Surface) {
+        if self.is_frozen() {
             return;
         }
 
-        self.acceleration += *push_vec * 10.;
         self.pos += *push_vec;
+        self.vel += surface.get_velocity_change(self.vel, push_vec.normalize());
+        if let Some(slip) =
+            surface.get_held_slip(self.displacement(), push_vec.normalize(), push_vec.length())
+        {
+            self.pos -= slip;
+        }
     }
 
     pub fn draw(&self) {
-        draw_circle(self.pos[0], self.pos[1], self.radius, YELLOW);
+        draw_circle(
+            self.pos[0],
+            self.pos[1],
+            self.radius,
+            if self.is_pinned() { RED } else { YELLOW },
+        );
     }
 
-    fn is_in_bounding_box(&self, polygon: &Polygon) -> bool {
+    pub fn is_in_bounding_box(&self, bounding_box: &BoundingBox) -> bool {
         let BoundingBox {
             min_hor,
             max_hor,
             min_ver,
             max_ver,
-        } = polygon.bounding_box;
+        } = *bounding_box;
         (self.pos[0] - self.radius) < max_hor
             && (self.pos[0] + self.radius) > min_hor
             && (self.pos[1] - self.radius) < max_ver
@@ -85,56 +170,85 @@ impl Dot {
     }
 
     pub fn is_center_inside_polygon(&self, polygon: &Polygon) -> bool {
-        let counter = polygon
-            .segments()
+        self.is_center_inside_segments(&polygon.segments())
+    }
+
+    fn is_center_inside_segments(&self, segments: &[Segment]) -> bool {
+        let counter = segments
             .iter()
             .fold(0, |acc, cur| acc + cur.vertical_raycast(&self.pos) as i32);
 
         counter % 2 != 0
     }
 
-    // Gets the closest projection of the Dot on any segment of a Polygon
-    fn get_closest_projection(&self, polygon: &Polygon) -> Vec2 {
-        let projections = polygon
-            .segments()
+    // Gets the closest projection of the Dot on any of the segments,
+    // along with the index of the segment it belongs to
+    fn get_closest_projection(&self, segments: &[Segment]) -> (usize, Vec2) {
+        segments
             .iter()
-            .map(|segment| segment.get_projection(&self.pos))
-            .filter(|projection| projection.is_some())
-            .map(|projection| projection.unwrap() - self.pos)
-            .collect::<Vec<Vec2>>();
-
-        projections.iter().fold(
-            vec2(f32::INFINITY, f32::INFINITY),
-            |distance1, &distance2| {
-                if distance1.length() < distance2.length() {
-                    distance1
-                } else {
-                    distance2
-                }
-            },
-        )
+            .enumerate()
+            .filter_map(|(index, segment)| {
+                segment
+                    .get_projection(&self.pos)
+                    .map(|projection| (index, projection - self.pos))
+            })
+            .fold(
+                (0, vec2(f32::INFINITY, f32::INFINITY)),
+                |closest, current| {
+                    if closest.1.length() < current.1.length() {
+                        closest
+                    } else {
+                        current
+                    }
+                },
+            )
     }
 
     // Calculates push vector to move Dot out of a Polygon
     pub fn get_push_vector(&self, polygon: &Polygon) -> Option<Vec2> {
-        if !self.is_in_bounding_box(polygon) {
+        if !self.is_in_bounding_box(&polygon.bounding_box) {
             return None;
         }
 
-        let closest_projection = self.get_closest_projection(polygon);
+        self.get_border_push_vector(&polygon.segments())
+            .map(|(_, push_vec)| push_vec)
+    }
+
+    // Calculates push vector to move Dot out of the shape closed by the segments,
+    // along with the index of the segment it is pushed away from
+    pub fn get_border_push_vector(&self, segments: &[Segment]) -> Option<(usize, Vec2)> {
+        let (index, closest_projection) = self.get_closest_projection(segments);
         let radius_projection = closest_projection.normalize() * self.radius;
 
-        let is_center_inside = self.is_center_inside_polygon(polygon);
+        let is_center_inside = self.is_center_inside_segments(segments);
         let is_partially_inside = closest_projection.length() < self.radius;
 
         if is_center_inside {
-            return Some(closest_projection + radius_projection);
+            return Some((index, closest_projection + radius_projection));
         }
 
         if is_partially_inside {
-            return Some(closest_projection - radius_projection);
+            return Some((index, closest_projection - radius_projection));
         }
 
         None
     }
+
+    // Calculates push vector to keep Dot on the side of a segment it was on before the last update
+    pub fn get_segment_push_vector(&self, segment: &Segment) -> Option<Vec2> {
+        let projection = segment.get_projection(&self.pos)?;
+        let direction = segment.p2 - segment.p1;
+        let previous_side = direction.perp_dot(self.prev_pos - segment.p1);
+        if previous_side == 0. {
+            return None;
+        }
+
+        let normal = direction.perp().normalize() * previous_side.signum();
+        let distance = (self.pos - projection).dot(normal);
+        if distance < self.radius {
+            Some(normal * (self.radius - distance))
+        } else {
+            None
+        }
+    }
 }
@@ -0,0 +1,94 @@
+use super::soft_body::*;
+use macroquad::prelude::*;
+
+pub const DEFAULT_DRAG_STIFFNESS: f32 = 2.;
+pub const DEFAULT_DRAG_DAMPING: f32 = 1.;
+
+// Spring between the mouse and the dot of a soft body it grabbed
+pub struct Drag {
+    pub stiffness: f32,
+    pub damping: f32,
+    // Indices of the body and of its dot being dragged
+    grabbed: Option<(usize, usize)>,
+    target: Vec2,
+    target_vel: Vec2,
+}
+
+impl Drag {
+    pub fn new(stiffness: Option<f32>, damping: Option<f32>) -> Drag {
+        Drag {
+            stiffness: stiffness.unwrap_or(DEFAULT_DRAG_STIFFNESS),
+            damping: damping.unwrap_or(DEFAULT_DRAG_DAMPING),
+            grabbed: None,
+            target: vec2(0., 0.),
+            target_vel: vec2(0., 0.),
+        }
+    }
+
+    pub fn grabbed(&self) -> Option<(usize, usize)> {
+        self.grabbed
+    }
+
+    pub fn grab(&mut self, body: usize, dot: usize) {
+        self.grabbed = Some((body, dot));
+    }
+
+    // Moves the end of the spring, keeping track of its velocity over the elapsed simulation time
+    pub fn move_to(&mut self, target: Vec2, delta_t: f32) {
+        if delta_t > 0. {
+            self.target_vel = (target - self.target) / delta_t;
+        }
+        self.target = target;
+    }
+
+    // Lets the body go with the velocity of the end of the spring, keeping its spin
+    pub fn release(&mut self, bodies: &mut [SoftBody]) {
+        if let Some(body) = self.grabbed.and_then(|(body, _)| bodies.get_mut(body)) {
+            let moving_points = body.points.iter().filter(|point| !point.is_frozen());
+            let count = moving_points.clone().count();
+            if count > 0 {
+                let mean_vel =
+                    moving_points.fold(vec2(0., 0.), |acc, point| acc + point.vel) / count as f32;
+                let velocity_change = self.target_vel - mean_vel;
+                body.points
+                    .iter_mut()
+                    .for_each(|point| point.add_velocity(&velocity_change));
+            }
+        }
+        self.grabbed = None;
+    }
+
+    // Lets go of a body whose dots were renumbered
+    pub fn let_go(&mut self, index: usize) {
+        if matches!(self.grabbed, Some((body, _)) if body == index) {
+            self.grabbed = None;
+        }
+    }
+
+    // Keeps the indices valid when a body is removed from the world
+    pub fn forget_body(&mut self, index: usize) {
+        self.grabbed = match self.grabbed {
+            Some((body, _)) if body == index => None,
+            Some((body, dot)) if body > index => Some((body - 1, dot)),
+            grabbed => grabbed,
+        };
+    }
+
+    pub fn apply(&self, bodies: &mut [SoftBody]) {
+        if let Some((body, dot)) = self.grabbed {
+            let point = &mut bodies[body].points[dot];
+            let force = (self.target - point.pos) * self.stiffness
+                + (self.target_vel - point.vel) * self.damping;
+            let acceleration = force * point.inverse_mass();
+            point.add_acceleration(acceleration);
+        }
+    }
+
+    pub fn draw(&self, bodies: &[SoftBody]) {
+        if let Some((body, dot)) = self.grabbed {
+            let pos = bodies[body].points[dot].pos;
+            draw_line(pos[0], pos[1], self.target[0], self.target[1], 2., SKYBLUE);
+            draw_circle(pos[0], pos[1], 4., SKYBLUE);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use super::material::Material;
 use super::polygon::*;
 use super::segment::*;
 use super::soft_body::SoftBody;
@@ -23,7 +24,7 @@ impl IncompletePolygon {
         Self { points: vec![] }
     }
 
-    pub fn draw(&self, polygons: &[Polygon], soft_body: &SoftBody) {
+    pub fn draw(&self, polygons: &[Polygon], bodies: &[SoftBody]) {
         self.points.iter().enumerate().for_each(|(i, point)| {
             let is_last_segment = i == self.points.len() - 1;
             let is_on_end = self.is_on_start();
@@ -41,7 +42,7 @@ impl IncompletePolygon {
             let drawing_color = if !is_last_segment {
                 STD_COLOR
             } else if self.is_intersecting_with_polygons(polygons)
-                || self.is_intersecting_with_soft_body(soft_body)
+                || self.is_intersecting_with_soft_bodies(bodies)
             {
                 ERROR_COLOR
             } else {
@@ -74,24 +75,19 @@ impl IncompletePolygon {
             })
     }
 
-    pub fn is_intersecting_with_soft_body(&self, body: &SoftBody) -> bool {
+    pub fn is_intersecting_with_soft_bodies(&self, bodies: &[SoftBody]) -> bool {
         !self.points.is_empty()
-            && body
-                .springs
-                .iter()
-                .filter(|spring| spring.is_on_border)
-                .any(|spring| {
+            && bodies.iter().any(|body| {
+                body.border_segments().iter().any(|segment| {
                     do_segments_intersect(
-                        &Segment {
-                            p1: body.points[spring.index_1].pos,
-                            p2: body.points[spring.index_2].pos,
-                        },
+                        segment,
                         &Segment {
                             p1: self.points[self.points.len() - 1],
                             p2: vec2(mouse_position().0, mouse_position().1),
                         },
                     )
                 })
+            })
     }
 
     // Checking if the mouse is close to the starting point
@@ -100,20 +96,33 @@ impl IncompletePolygon {
             && (vec2(mouse_position().0, mouse_position().1) - self.points[0]).length() < 30.
     }
 
-    pub fn add_point(&mut self, pos: Vec2, polygons: &mut Vec<Polygon>) {
+    // Adds the point to the outline, or hands the outline over when it gets closed
+    fn extend(&mut self, pos: Vec2) -> Option<Vec<Vec2>> {
         if !self.is_on_start() {
             self.points.push(pos);
-            return;
+            return None;
         }
+        Some(std::mem::take(&mut self.points))
+    }
 
-        polygons.push(Polygon::new(
-            self.points
-                .iter()
-                .map(|vector| (vector[0], vector[1]))
-                .collect(),
-            None,
-        ));
-        self.reset();
+    pub fn add_point(&mut self, pos: Vec2, polygons: &mut Vec<Polygon>) {
+        if let Some(outline) = self.extend(pos) {
+            polygons.push(Polygon::new(
+                outline
+                    .iter()
+                    .map(|vector| (vector[0], vector[1]))
+                    .collect(),
+                None,
+            ));
+        }
+    }
+
+    // Same as adding a point to a polygon, filling the closed outline with a soft body instead.
+    // Outlines enclosing nothing are dropped
+    pub fn add_body_point(&mut self, pos: Vec2, bodies: &mut Vec<SoftBody>, material: &Material) {
+        if let Some(outline) = self.extend(pos) {
+            bodies.extend(SoftBody::from_outline(&outline, None, Some(*material)));
+        }
     }
 
     pub fn reset(&mut self) {
@@ -1,23 +1,251 @@
 use super::dot::*;
 use super::polygon::*;
+use super::segment::*;
+use super::soft_body::*;
 use macroquad::prelude::*;
 
-pub fn handle_point_polygon_collision(point: &mut Dot, polygon: &Polygon) {
+pub const DEFAULT_STATIC_FRICTION: f32 = 0.6;
+pub const DEFAULT_KINETIC_FRICTION: f32 = 0.4;
+pub const DEFAULT_RESTITUTION: f32 = 0.3;
+pub const RESTING_SPEED: f32 = 2.5;
+
+// Contact properties of a polygon or a soft body
+#[derive(Copy, Clone, Debug)]
+pub struct Surface {
+    pub static_friction: f32,
+    pub kinetic_friction: f32,
+    pub restitution: f32,
+}
+
+impl Default for Surface {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_STATIC_FRICTION,
+            DEFAULT_KINETIC_FRICTION,
+            DEFAULT_RESTITUTION,
+        )
+    }
+}
+
+impl Surface {
+    pub fn new(static_friction: f32, kinetic_friction: f32, restitution: f32) -> Self {
+        Self {
+            static_friction,
+            kinetic_friction,
+            restitution,
+        }
+    }
+
+    // Properties of the contact between two surfaces
+    pub fn combine(&self, other: &Surface) -> Surface {
+        Surface {
+            static_friction: (self.static_friction * other.static_friction).sqrt(),
+            kinetic_friction: (self.kinetic_friction * other.kinetic_friction).sqrt(),
+            restitution: self.restitution.max(other.restitution),
+        }
+    }
+
+    // Change of relative velocity needed to bounce and rub along the contact normal,
+    // with the friction bounded by the normal change as in Coulomb's model
+    pub fn get_velocity_change(&self, relative_vel: Vec2, normal: Vec2) -> Vec2 {
+        let normal_speed = relative_vel.dot(normal);
+        if normal_speed >= 0. {
+            return vec2(0., 0.);
+        }
+
+        // Resting contacts, only approaching by what a step of the load on them adds, stay put
+        let bounce = (-normal_speed - RESTING_SPEED).max(0.) * self.restitution;
+        let normal_change = -normal_speed + bounce;
+        let tangent_vel = relative_vel - normal * normal_speed;
+        let tangent_speed = tangent_vel.length();
+        let tangent_change = if tangent_speed <= self.static_friction * normal_change {
+            -tangent_vel
+        } else {
+            -tangent_vel * (self.kinetic_friction * normal_change / tangent_speed)
+        };
+
+        normal * normal_change + tangent_change
+    }
+
+    // Slip along the contact over the step, if the static friction holds against it given
+    // the depth of the contact, to be taken back so that resting bodies do not creep
+    pub fn get_held_slip(&self, slip: Vec2, normal: Vec2, depth: f32) -> Option<Vec2> {
+        let tangent_slip = slip - normal * slip.dot(normal);
+        if tangent_slip.length() <= self.static_friction * depth {
+            Some(tangent_slip)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn handle_point_polygon_collision(point: &mut Dot, polygon: &Polygon, surface: &Surface) {
     match point.get_push_vector(polygon) {
         None => (),
         Some(vector) => {
-            point.push(&vector);
+            point.resolve_contact(&vector, surface);
         }
     }
 }
 
-pub fn handle_point_point_collision(points: &mut Vec<Dot>, index_1: usize, index_2: usize) {
+pub fn handle_body_body_collision(body_1: &mut SoftBody, body_2: &mut SoftBody) {
+    let BoundingBox {
+        min_hor,
+        max_hor,
+        min_ver,
+        max_ver,
+    } = body_1.bounding_box();
+    let other = body_2.bounding_box();
+    // The dots touch the other border as soon as their radius reaches it
+    let margin = body_1.material().radius.max(body_2.material().radius);
+    if min_hor > other.max_hor + margin
+        || max_hor < other.min_hor - margin
+        || min_ver > other.max_ver + margin
+        || max_ver < other.min_ver - margin
+    {
+        return;
+    }
+
+    handle_points_border_collision(body_1, body_2);
+    handle_points_border_collision(body_2, body_1);
+}
+
+// Pushes the dots of a body out of the border of another one
+fn handle_points_border_collision(body: &mut SoftBody, other: &mut SoftBody) {
+    let surface = body.surface.combine(&other.surface);
+    let bounding_box = other.bounding_box();
+    let border = other.border_springs();
+    let segments = other.border_segments();
+
+    body.points
+        .iter_mut()
+        .filter(|point| point.is_in_bounding_box(&bounding_box))
+        .for_each(|point| {
+            if let Some((index, push_vec)) = point.get_border_push_vector(&segments) {
+                let Spring {
+                    index_1, index_2, ..
+                } = border[index];
+                let (mut end_1, mut end_2) = (other.points[index_1], other.points[index_2]);
+
+                resolve_border_contact(
+                    point,
+                    (&mut end_1, &mut end_2),
+                    push_vec,
+                    segments[index].get_ratio(&point.pos),
+                    &surface,
+                );
+                other.points[index_1] = end_1;
+                other.points[index_2] = end_2;
+            }
+        });
+}
+
+// Keeps the border dots of a body from crossing the border springs they are not part of
+pub fn handle_self_collision(body: &mut SoftBody) {
+    let surface = body.surface.combine(&body.surface);
+    let border = body.border_springs();
+    let mut border_dots = border
+        .iter()
+        .flat_map(|spring| [spring.index_1, spring.index_2])
+        .collect::<Vec<usize>>();
+    border_dots.sort_unstable();
+    border_dots.dedup();
+
+    border_dots.iter().for_each(|&index| {
+        border
+            .iter()
+            .filter(|spring| spring.index_1 != index && spring.index_2 != index)
+            .for_each(|spring| {
+                let segment = Segment {
+                    p1: body.points[spring.index_1].pos,
+                    p2: body.points[spring.index_2].pos,
+                };
+                if let Some(push_vec) = body.points[index].get_segment_push_vector(&segment) {
+                    let (mut point, mut end_1, mut end_2) = (
+                        body.points[index],
+                        body.points[spring.index_1],
+                        body.points[spring.index_2],
+                    );
+                    let ratio = segment.get_ratio(&point.pos);
+
+                    resolve_border_contact(
+                        &mut point,
+                        (&mut end_1, &mut end_2),
+                        push_vec,
+                        ratio,
+                        &surface,
+                    );
+                    body.points[index] = point;
+                    body.points[spring.index_1] = end_1;
+                    body.points[spring.index_2] = end_2;
+                }
+            });
+    });
+}
+
+// Splits the correction of a contact between a dot and the ends of the border spring it hit
+// by their inverse masses, along with the velocity change given by the surface of the contact
+fn resolve_border_contact(
+    point: &mut Dot,
+    (end_1, end_2): (&mut Dot, &mut Dot),
+    push_vec: Vec2,
+    ratio: f32,
+    surface: &Surface,
+) {
+    let weights = (
+        point.inverse_mass(),
+        end_1.inverse_mass() * (1. - ratio),
+        end_2.inverse_mass() * ratio,
+    );
+    let total_weight = weights.0 + weights.1 * (1. - ratio) + weights.2 * ratio;
+    if total_weight == 0. {
+        return;
+    }
+
+    let border_vel = end_1.vel * (1. - ratio) + end_2.vel * ratio;
+    let impulse =
+        surface.get_velocity_change(point.vel - border_vel, push_vec.normalize()) / total_weight;
+    point.add_velocity(&(impulse * weights.0));
+    end_1.add_velocity(&(-impulse * weights.1));
+    end_2.add_velocity(&(-impulse * weights.2));
+
+    let depth = push_vec.length();
+    let push_vec = push_vec / total_weight;
+    point.shift(&(push_vec * weights.0));
+    end_1.shift(&(-push_vec * weights.1));
+    end_2.shift(&(-push_vec * weights.2));
+
+    let slip =
+        point.displacement() - (end_1.displacement() * (1. - ratio) + end_2.displacement() * ratio);
+    if let Some(tangent_slip) = surface.get_held_slip(slip, push_vec.normalize(), depth) {
+        let tangent_slip = tangent_slip / total_weight;
+        point.shift(&(-tangent_slip * weights.0));
+        end_1.shift(&(tangent_slip * weights.1));
+        end_2.shift(&(tangent_slip * weights.2));
+    }
+}
+
+// Moves the ends of a Spring apart when closer than the radius of the dots, each by its share of
+// the overlap, and stops them from closing in further
+pub fn handle_point_point_collision(points: &mut [Dot], index_1: usize, index_2: usize) {
     let distance = points[index_1].pos - points[index_2].pos;
-    let radius = points[index_1].radius;
-    if distance.length() < points[index_1].radius && distance.length() > 0. {
-        points[index_1].push(&(distance.normalize() * radius));
-        points[index_2].push(&(-distance.normalize() * radius));
+    let overlap = points[index_1].radius - distance.length();
+    let total_weight = points[index_1].inverse_mass() + points[index_2].inverse_mass();
+    if overlap <= 0. || distance.length() == 0. || total_weight == 0. {
+        return;
     }
+
+    let normal = distance.normalize();
+    let closing_speed = (points[index_1].vel - points[index_2].vel)
+        .dot(normal)
+        .min(0.);
+    [(index_1, 1.), (index_2, -1.)]
+        .into_iter()
+        .for_each(|(index, side)| {
+            let share = points[index].inverse_mass() / total_weight;
+            points[index].shift(&(normal * side * overlap * share));
+            points[index].add_velocity(&(-normal * side * closing_speed * share));
+        });
 }
 
 pub fn handle_temp_point_point_collision(pos1: Vec2, pos2: Vec2) -> Option<Vec2> {
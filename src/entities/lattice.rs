@@ -0,0 +1,451 @@
+use super::dot::*;
+use super::material::Material;
+use super::soft_body::*;
+use crate::utils::math::signed_area;
+use crate::utils::mesh::*;
+use macroquad::prelude::*;
+use std::f32::consts::PI;
+
+// How the dots of a generated body are packed
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Lattice {
+    // Square cells, held as two triangles by a diagonal, the other one resisting shear
+    #[default]
+    Square,
+    // Rows of dots shifted by half a cell, making triangular cells that respond the same
+    // way in every direction
+    Triangular,
+    // Honeycomb of hexagonal cells, held as triangles by the springs from one of their
+    // corners, the other springs across their corners resisting shear and folds
+    Hexagonal,
+}
+
+// Smallest cells, as wide as a dot, so that the neighbouring dots stay clear of each other
+pub const MIN_CELL_SIZE: f32 = 2. * RADIUS;
+
+// How finely a generated body is divided into cells
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Resolution {
+    // Cells of about this size, kept regular
+    CellSize(f32),
+    // Numbers of cells across and down, stretched to fill the body
+    Cells(usize, usize),
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Self::CellSize(DEFAULT_SPACING)
+    }
+}
+
+pub fn lattices() -> Vec<(&'static str, Lattice)> {
+    vec![
+        ("Square", Lattice::Square),
+        ("Triangular", Lattice::Triangular),
+        ("Hexagonal", Lattice::Hexagonal),
+    ]
+}
+
+// Dots of the lattice filling the rectangle, the springs between them, inactive for the kinds
+// the material lacks, and the triangles covering the cells
+pub fn generate_lattice(
+    lattice: Lattice,
+    top_left_corner: Vec2,
+    bottom_right_corner: Vec2,
+    resolution: Resolution,
+    material: &Material,
+) -> (Vec<Dot>, Vec<Spring>, Vec<[usize; 3]>) {
+    match lattice {
+        Lattice::Square => {
+            let (columns, rows) = square_cells(bottom_right_corner - top_left_corner, resolution);
+            let mut dots = generate_dots(top_left_corner, bottom_right_corner, (columns, rows));
+            let mut springs = generate_springs(&dots, (columns, rows), material, true);
+            let stretch =
+                (bottom_right_corner - top_left_corner) / vec2(columns as f32, rows as f32);
+            let quads = get_square_quads((columns, rows));
+            weigh_dots(&mut dots, &quads, material);
+            stiffen_stretched_cells(&dots, &mut springs, stretch, material);
+            let triangles = quads
+                .into_iter()
+                .flat_map(|[a, b, c, d]| [[a, b, c], [a, c, d]])
+                .collect();
+            (dots, springs, triangles)
+        }
+        Lattice::Triangular => {
+            let (positions, triangles, stretch) =
+                generate_triangular_cells(top_left_corner, bottom_right_corner, resolution);
+            let (mut dots, mut springs) = connect_cells(&positions, &triangles, &[], material);
+            weigh_dots(&mut dots, &triangles, material);
+            stiffen_stretched_cells(&dots, &mut springs, stretch, material);
+            (dots, springs, triangles)
+        }
+        Lattice::Hexagonal => {
+            let (positions, hexagons, stretch) =
+                generate_hexagonal_cells(top_left_corner, bottom_right_corner, resolution);
+            // Fanning out of the first corner, the structural springs make the cell rigid
+            let across_corners = hexagons
+                .iter()
+                .flat_map(|hexagon| {
+                    let corners = |pairs: &[(usize, usize)], kind: SpringKind| {
+                        pairs
+                            .iter()
+                            .map(|&(start, end)| ((hexagon[start], hexagon[end]), kind))
+                            .collect::<Vec<((usize, usize), SpringKind)>>()
+                    };
+                    [
+                        corners(&[(0, 2), (0, 3), (0, 4)], SpringKind::Structural),
+                        corners(&[(1, 3), (2, 4), (3, 5), (5, 1)], SpringKind::Shear),
+                        corners(&[(1, 4), (2, 5)], SpringKind::Bend),
+                    ]
+                    .concat()
+                })
+                .collect::<Vec<((usize, usize), SpringKind)>>();
+            let triangles = hexagons
+                .iter()
+                .flat_map(|hexagon| {
+                    (1..5).map(move |corner| [hexagon[0], hexagon[corner], hexagon[corner + 1]])
+                })
+                .collect();
+            let (mut dots, mut springs) =
+                connect_cells(&positions, &hexagons, &across_corners, material);
+            weigh_dots(&mut dots, &hexagons, material);
+            stiffen_stretched_cells(&dots, &mut springs, stretch, material);
+            (dots, springs, triangles)
+        }
+    }
+}
+
+// Square grid of dots filling the rectangle, without any border, along with its cells
+// as the corners around them and the indices of the dots of its top row
+pub fn generate_grid(
+    top_left_corner: Vec2,
+    bottom_right_corner: Vec2,
+    resolution: Resolution,
+    material: &Material,
+) -> (Vec<Dot>, Vec<Spring>, Vec<[usize; 4]>, Vec<usize>) {
+    let (columns, rows) = square_cells(bottom_right_corner - top_left_corner, resolution);
+    let mut dots = generate_dots(top_left_corner, bottom_right_corner, (columns, rows));
+    let mut springs = generate_springs(&dots, (columns, rows), material, false);
+    let stretch = (bottom_right_corner - top_left_corner) / vec2(columns as f32, rows as f32);
+    let quads = get_square_quads((columns, rows));
+    weigh_dots(&mut dots, &quads, material);
+    stiffen_stretched_cells(&dots, &mut springs, stretch, material);
+    let top_row = (0..=columns).map(|column| column * (rows + 1)).collect();
+    (dots, springs, quads, top_row)
+}
+
+// Gives every dot an equal share of the area of the cells around it, weighing the mass of the
+// material for each square of the default spacing on a side, so that the mass of a body only
+// depends on its area and not on how finely it is divided
+pub fn weigh_dots<T: AsRef<[usize]>>(dots: &mut [Dot], cells: &[T], material: &Material) {
+    let mut areas = vec![0.; dots.len()];
+    cells.iter().for_each(|cell| {
+        let cell = cell.as_ref();
+        let corners = cell
+            .iter()
+            .map(|&index| dots[index].pos)
+            .collect::<Vec<Vec2>>();
+        let share = signed_area(&corners).abs() / cell.len() as f32;
+        cell.iter().for_each(|&index| areas[index] += share);
+    });
+    dots.iter_mut()
+        .zip(areas)
+        .for_each(|(dot, area)| dot.set_mass(material.mass * area / DEFAULT_SPACING.powi(2)));
+}
+
+// Gives the springs of cells stretched from the lattice of unit cells the stiffness keeping
+// the body as stiff as a regular one
+fn stiffen_stretched_cells(
+    dots: &[Dot],
+    springs: &mut [Spring],
+    stretch: Vec2,
+    material: &Material,
+) {
+    if stretch.x == stretch.y {
+        return;
+    }
+    springs.iter_mut().for_each(|spring| {
+        let vector = dots[spring.index_2].pos - dots[spring.index_1].pos;
+        let unit_vector = vector / stretch;
+        let scale = stretch.x * stretch.y * unit_vector.length_squared() / vector.length_squared();
+        spring.set_stiffness_scale(scale, material);
+    });
+}
+
+// Corners of the square cells of the lattice, starting from the top left one
+fn get_square_quads((columns, rows): (usize, usize)) -> Vec<[usize; 4]> {
+    let index = |column: usize, row: usize| column * (rows + 1) + row;
+    (0..columns)
+        .flat_map(|column| (0..rows).map(move |row| (column, row)))
+        .map(|(column, row)| {
+            [
+                index(column, row),
+                index(column + 1, row),
+                index(column + 1, row + 1),
+                index(column, row + 1),
+            ]
+        })
+        .collect()
+}
+
+// Numbers of square cells across and down the rectangle
+fn square_cells(size: Vec2, resolution: Resolution) -> (usize, usize) {
+    match resolution {
+        Resolution::CellSize(cell_size) => (count(size.x / cell_size), count(size.y / cell_size)),
+        Resolution::Cells(columns, rows) => (columns.max(1), rows.max(1)),
+    }
+}
+
+// Number of whole cells closest to the fraction, at least one
+fn count(cells: f32) -> usize {
+    (cells.round() as usize).max(1)
+}
+
+// Generates the dots of the lattice column by column
+fn generate_dots(
+    top_left_corner: Vec2,
+    bottom_right_corner: Vec2,
+    (columns, rows): (usize, usize),
+) -> Vec<Dot> {
+    let step = (bottom_right_corner - top_left_corner) / vec2(columns as f32, rows as f32);
+    (0..=columns)
+        .flat_map(|column| (0..=rows).map(move |row| (column, row)))
+        .map(|(column, row)| Dot::new(top_left_corner + vec2(column as f32, row as f32) * step))
+        .collect()
+}
+
+// Connects the dots of the lattice with the springs along the sides of the cells and along
+// a diagonal holding them, and with the shear and bend springs
+fn generate_springs(
+    dots: &[Dot],
+    (columns, rows): (usize, usize),
+    material: &Material,
+    has_border: bool,
+) -> Vec<Spring> {
+    let index = |column: usize, row: usize| column * (rows + 1) + row;
+    let mut springs = vec![];
+    let mut connect = |ends: ((usize, usize), (usize, usize)), kind, is_on_border| {
+        springs.push(Spring::new(
+            dots,
+            (index(ends.0 .0, ends.0 .1), index(ends.1 .0, ends.1 .1)),
+            material,
+            kind,
+            is_on_border,
+        ));
+    };
+
+    (0..=columns).for_each(|column| {
+        (0..=rows).for_each(|row| {
+            if column < columns {
+                connect(
+                    ((column, row), (column + 1, row)),
+                    SpringKind::Structural,
+                    has_border && (row == 0 || row == rows),
+                );
+            }
+            if row < rows {
+                connect(
+                    ((column, row), (column, row + 1)),
+                    SpringKind::Structural,
+                    has_border && (column == 0 || column == columns),
+                );
+            }
+            if column < columns && row < rows {
+                connect(
+                    ((column, row), (column + 1, row + 1)),
+                    SpringKind::Structural,
+                    false,
+                );
+                connect(
+                    ((column + 1, row), (column, row + 1)),
+                    SpringKind::Shear,
+                    false,
+                );
+            }
+            if column + 1 < columns {
+                connect(((column, row), (column + 2, row)), SpringKind::Bend, false);
+            }
+            if row + 1 < rows {
+                connect(((column, row), (column, row + 2)), SpringKind::Bend, false);
+            }
+        });
+    });
+
+    springs
+}
+
+// Rows of dots a cell apart, every other one shifted by half a cell and one dot shorter,
+// along with the triangles between the rows and their scale from unit equilateral triangles.
+// Given a cell size the triangles are kept equilateral, the rows being centered in the height
+// of the rectangle
+fn generate_triangular_cells(
+    top_left_corner: Vec2,
+    bottom_right_corner: Vec2,
+    resolution: Resolution,
+) -> (Vec<Vec2>, Vec<[usize; 3]>, Vec2) {
+    let size = bottom_right_corner - top_left_corner;
+    let (columns, rows, row_height) = match resolution {
+        Resolution::CellSize(cell_size) => {
+            let columns = count(size.x / cell_size);
+            let row_height = size.x / columns as f32 * 3f32.sqrt() / 2.;
+            (columns, count(size.y / row_height), row_height)
+        }
+        Resolution::Cells(columns, rows) => {
+            (columns.max(1), rows.max(1), size.y / rows.max(1) as f32)
+        }
+    };
+    let spacing = size.x / columns as f32;
+    let top_left_corner = top_left_corner + vec2(0., (size.y - rows as f32 * row_height) / 2.);
+
+    let mut positions = vec![];
+    let row_indices = (0..=rows)
+        .map(|row| {
+            let shift = row % 2;
+            (0..columns + 1 - shift)
+                .map(|column| {
+                    positions.push(
+                        top_left_corner
+                            + vec2(
+                                (column as f32 + shift as f32 / 2.) * spacing,
+                                row as f32 * row_height,
+                            ),
+                    );
+                    positions.len() - 1
+                })
+                .collect::<Vec<usize>>()
+        })
+        .collect::<Vec<Vec<usize>>>();
+
+    let triangles = row_indices
+        .windows(2)
+        .enumerate()
+        .flat_map(|(row, pair)| {
+            let (long, short) = if row % 2 == 0 {
+                (&pair[0], &pair[1])
+            } else {
+                (&pair[1], &pair[0])
+            };
+            (0..columns)
+                .map(|column| [long[column], long[column + 1], short[column]])
+                .chain(
+                    (0..columns - 1)
+                        .map(|column| [short[column], short[column + 1], long[column + 1]]),
+                )
+                .collect::<Vec<[usize; 3]>>()
+        })
+        .collect();
+    let stretch = vec2(spacing, row_height / (3f32.sqrt() / 2.));
+    (positions, triangles, stretch)
+}
+
+// Columns of hexagons with flat tops, every other one shifted by half a hexagon, filling the
+// width of the rectangle. Given a cell size the hexagons are kept regular, centered in the
+// height of the rectangle. The hexagons are given by their corners in order, along with their
+// scale from hexagons of unit side
+fn generate_hexagonal_cells(
+    top_left_corner: Vec2,
+    bottom_right_corner: Vec2,
+    resolution: Resolution,
+) -> (Vec<Vec2>, Vec<Vec<usize>>, Vec2) {
+    let size = bottom_right_corner - top_left_corner;
+    let height = 3f32.sqrt();
+    let (columns, rows) = match resolution {
+        Resolution::CellSize(cell_size) => {
+            // Side giving about as many dots as square cells of that size
+            let side = cell_size * 0.8;
+            let columns = count((size.x / side - 0.5) / 1.5);
+            let shifted = if columns > 1 { 0.5 } else { 0. };
+            (columns, count(size.y / (height * side) - shifted))
+        }
+        Resolution::Cells(columns, rows) => (columns.max(1), rows.max(1)),
+    };
+    let shifted = if columns > 1 { 0.5 } else { 0. };
+
+    // Hexagons of unit side, moved into the rectangle afterwards
+    let mut positions = vec![];
+    let hexagons = (0..columns)
+        .flat_map(|column| (0..rows).map(move |row| (column, row)))
+        .map(|(column, row)| {
+            let center = vec2(
+                1. + 1.5 * column as f32,
+                height * (0.5 + row as f32 + (column % 2) as f32 / 2.),
+            );
+            (0..6)
+                .map(|corner| {
+                    let angle = PI / 3. * corner as f32;
+                    add_point(&mut positions, center + vec2(angle.cos(), angle.sin()))
+                })
+                .collect::<Vec<usize>>()
+        })
+        .collect::<Vec<Vec<usize>>>();
+
+    let unit_size = vec2(1.5 * columns as f32 + 0.5, height * (rows as f32 + shifted));
+    let scale = match resolution {
+        Resolution::CellSize(_) => Vec2::splat(size.x / unit_size.x),
+        Resolution::Cells(_, _) => size / unit_size,
+    };
+    let offset = vec2(0., (size.y - unit_size.y * scale.y) / 2.);
+    let positions = positions
+        .iter()
+        .map(|position| top_left_corner + offset + *position * scale)
+        .collect();
+    (positions, hexagons, scale)
+}
+
+// Connects the dots with springs along the sides of the cells, on the border when they are
+// the side of a single cell, and with the other springs
+fn connect_cells<T: AsRef<[usize]>>(
+    positions: &[Vec2],
+    cells: &[T],
+    other_springs: &[((usize, usize), SpringKind)],
+    material: &Material,
+) -> (Vec<Dot>, Vec<Spring>) {
+    let dots = positions
+        .iter()
+        .map(|pos| Dot::new(*pos))
+        .collect::<Vec<Dot>>();
+    let sides = get_edges(cells)
+        .into_iter()
+        .map(|(ends, is_on_border)| (ends, SpringKind::Structural, is_on_border));
+    let bends = get_straight_bends(positions, cells)
+        .into_iter()
+        .map(|ends| (ends, SpringKind::Bend, false));
+    let springs = sides
+        .chain(bends)
+        .chain(
+            other_springs
+                .iter()
+                .map(|&(ends, kind)| (ends, kind, false)),
+        )
+        .map(|(ends, kind, is_on_border)| Spring::new(&dots, ends, material, kind, is_on_border))
+        .collect();
+    (dots, springs)
+}
+
+// Pairs of dots with a dot right between them along the sides of the cells
+fn get_straight_bends<T: AsRef<[usize]>>(positions: &[Vec2], cells: &[T]) -> Vec<(usize, usize)> {
+    let edges = get_edges(cells);
+    let neighbours = |index: usize| {
+        edges.iter().filter_map(move |&((index_1, index_2), _)| {
+            if index_1 == index {
+                Some(index_2)
+            } else if index_2 == index {
+                Some(index_1)
+            } else {
+                None
+            }
+        })
+    };
+    (0..positions.len())
+        .flat_map(|middle| {
+            neighbours(middle)
+                .flat_map(|start| neighbours(middle).map(move |end| (start, end)))
+                .filter(|&(start, end)| {
+                    start < end
+                        && (positions[start] + positions[end] - 2. * positions[middle]).length()
+                            < (positions[end] - positions[start]).length() * 0.01
+                })
+                .collect::<Vec<(usize, usize)>>()
+        })
+        .collect()
+}
@@ -0,0 +1,144 @@
+use super::dot::RADIUS;
+use super::soft_body::SpringKind;
+
+pub const MAX_SPEED: f32 = 100.;
+pub const DEFAULT_SHAPE_STIFFNESS: f32 = 1.;
+pub const DEFAULT_BULK_MODULUS: f32 = 3.;
+pub const DEFAULT_TEAR_STRAIN: f32 = 0.8;
+pub const DEFAULT_YIELD_RATIO: f32 = 0.1;
+pub const DEFAULT_CREEP: f32 = 0.5;
+pub const DEFAULT_MAX_DEFORMATION: f32 = 0.5;
+
+// How the springs keep part of a deformation beyond their elastic limit
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plasticity {
+    // Strain the springs take before yielding
+    pub yield_ratio: f32,
+    // Share of the strain beyond the yield moved into the rest length per unit of time
+    pub creep: f32,
+    // Largest change of the rest length, relative to the original one
+    pub max_deformation: f32,
+}
+
+impl Default for Plasticity {
+    fn default() -> Self {
+        Self {
+            yield_ratio: DEFAULT_YIELD_RATIO,
+            creep: DEFAULT_CREEP,
+            max_deformation: DEFAULT_MAX_DEFORMATION,
+        }
+    }
+}
+
+// Physical parameters shared by the springs and the dots of a soft body
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Material {
+    pub stiffness: f32,
+    pub damping: f32,
+    // Stiffness of the springs on the border, which keep the shape of the body
+    pub border_stiffness: f32,
+    // Stiffness of the diagonal springs resisting shear and of the springs skipping a dot
+    // resisting folds, if the lattice has them
+    pub shear_stiffness: Option<f32>,
+    pub bend_stiffness: Option<f32>,
+    // Mass of a square of the default spacing on a side, shared between the dots by the area
+    // of their cells
+    pub mass: f32,
+    pub radius: f32,
+    pub max_speed: f32,
+    // Stiffness pulling the dots back to the rest shape of the body, if matching it
+    pub shape_matching: Option<f32>,
+    // Resistance of the cells of the body to a change of area, if preserving it
+    pub bulk_modulus: Option<f32>,
+    pub plasticity: Option<Plasticity>,
+    // Strain breaking the springs, if they can tear
+    pub tear_strain: Option<f32>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::rubber()
+    }
+}
+
+impl Material {
+    pub fn new(
+        stiffness: f32,
+        damping: f32,
+        border_stiffness: f32,
+        mass: f32,
+        radius: f32,
+        max_speed: f32,
+    ) -> Self {
+        Self {
+            stiffness,
+            damping,
+            border_stiffness,
+            shear_stiffness: Some(stiffness),
+            bend_stiffness: None,
+            mass,
+            radius,
+            max_speed,
+            shape_matching: None,
+            bulk_modulus: None,
+            plasticity: None,
+            tear_strain: None,
+        }
+    }
+
+    pub fn rubber() -> Self {
+        Self::new(10., 0.8, 12., 1., RADIUS, MAX_SPEED)
+    }
+
+    pub fn jelly() -> Self {
+        Self {
+            shape_matching: Some(0.3),
+            bulk_modulus: Some(DEFAULT_BULK_MODULUS),
+            ..Self::new(3., 0.3, 4., 1., RADIUS, MAX_SPEED)
+        }
+    }
+
+    pub fn stiff_foam() -> Self {
+        Self {
+            bend_stiffness: Some(10.),
+            shape_matching: Some(2.),
+            ..Self::new(20., 1.5, 24., 1.5, RADIUS, MAX_SPEED)
+        }
+    }
+
+    pub fn clay() -> Self {
+        Self {
+            plasticity: Some(Plasticity::default()),
+            ..Self::new(8., 1.5, 10., 1.5, RADIUS, MAX_SPEED)
+        }
+    }
+
+    // Light and stretchy, barely resisting folds
+    pub fn cloth() -> Self {
+        Self {
+            shear_stiffness: Some(3.),
+            bend_stiffness: Some(1.),
+            ..Self::new(8., 0.8, 8., 1., RADIUS, MAX_SPEED)
+        }
+    }
+
+    // Stiffness of a kind of spring, if the material has them
+    pub fn stiffness_of(&self, kind: SpringKind, is_on_border: bool) -> Option<f32> {
+        match kind {
+            SpringKind::Structural if is_on_border => Some(self.border_stiffness),
+            SpringKind::Structural => Some(self.stiffness),
+            SpringKind::Shear => self.shear_stiffness,
+            SpringKind::Bend => self.bend_stiffness,
+        }
+    }
+}
+
+pub fn presets() -> Vec<(&'static str, Material)> {
+    vec![
+        ("Rubber", Material::rubber()),
+        ("Jelly", Material::jelly()),
+        ("Stiff foam", Material::stiff_foam()),
+        ("Clay", Material::clay()),
+        ("Cloth", Material::cloth()),
+    ]
+}
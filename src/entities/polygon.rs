@@ -1,3 +1,4 @@
+use super::interaction::Surface;
 use super::segment::*;
 use crate::utils::conversion::*;
 use macroquad::prelude::*;
@@ -11,9 +12,14 @@ pub struct BoundingBox {
 }
 
 impl BoundingBox {
+    // Smallest box around the points, empty and overlapping nothing when there are none
     pub fn new(points: &[(f32, f32)]) -> Self {
-        let (mut min_hor, mut max_hor, mut min_ver, mut max_ver): (f32, f32, f32, f32) =
-            (points[0].0, points[0].0, points[0].1, points[0].1);
+        let (mut min_hor, mut max_hor, mut min_ver, mut max_ver): (f32, f32, f32, f32) = (
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+        );
         for point in points {
             min_hor = if min_hor < point.0 { min_hor } else { point.0 };
             max_hor = if max_hor > point.0 { max_hor } else { point.0 };
@@ -34,6 +40,7 @@ pub struct Polygon {
     points: Vec<(f32, f32)>,
     color: Color,
     pub bounding_box: BoundingBox,
+    pub surface: Surface,
 }
 
 impl Polygon {
@@ -42,16 +49,17 @@ impl Polygon {
             bounding_box: BoundingBox::new(&points),
             points,
             color: color.unwrap_or(BLUE),
+            surface: Surface::default(),
         }
     }
 
-    pub fn generate_floor() -> Polygon {
+    pub fn generate_floor(width: f32, height: f32) -> Polygon {
         Polygon::new(
             vec![
-                (20., screen_height() - 50.),
-                (screen_width() - 20., screen_height() - 50.),
-                (screen_width() - 20., screen_height() - 20.),
-                (20., screen_height() - 20.),
+                (20., height - 50.),
+                (width - 20., height - 50.),
+                (width - 20., height - 20.),
+                (20., height - 20.),
             ],
             None,
         )
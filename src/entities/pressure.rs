@@ -0,0 +1,65 @@
+use crate::utils::math::signed_area;
+use macroquad::prelude::*;
+
+pub const DEFAULT_PRESSURE: f32 = 0.6;
+
+// Gas filling the ring of dots of a body, following P·V = const
+#[derive(Clone, Debug)]
+pub struct Pressure {
+    // Indices of the dots around the gas, in order
+    pub ring: Vec<usize>,
+    // Product of the pressure and the area, constant for an ideal gas at a fixed temperature
+    pub amount: f32,
+}
+
+impl Pressure {
+    // Fills the ring so that the gas has the given pressure with the current positions
+    pub fn new(ring: Vec<usize>, positions: &[Vec2], pressure: Option<f32>) -> Pressure {
+        let mut gas = Pressure { ring, amount: 0. };
+        gas.amount = pressure.unwrap_or(DEFAULT_PRESSURE) * gas.get_area(positions);
+        gas
+    }
+
+    pub fn get_area(&self, positions: &[Vec2]) -> f32 {
+        signed_area(
+            &self
+                .ring
+                .iter()
+                .map(|&index| positions[index])
+                .collect::<Vec<Vec2>>(),
+        )
+        .abs()
+    }
+
+    // Force of the gas on every dot, pushing each side of the ring outwards in proportion
+    // to its length and split between its ends
+    pub fn get_forces(&self, positions: &[Vec2]) -> Vec<Vec2> {
+        let mut forces = vec![vec2(0., 0.); positions.len()];
+        let ring_positions = self
+            .ring
+            .iter()
+            .map(|&index| positions[index])
+            .collect::<Vec<Vec2>>();
+        let area = signed_area(&ring_positions);
+        if area == 0. {
+            return forces;
+        }
+
+        let pressure = self.amount / area.abs();
+        self.ring
+            .iter()
+            .zip(self.ring.iter().cycle().skip(1))
+            .for_each(|(&index_1, &index_2)| {
+                let side = positions[index_2] - positions[index_1];
+                let force = -side.perp() * area.signum() * pressure / 2.;
+                forces[index_1] += force;
+                forces[index_2] += force;
+            });
+
+        forces
+    }
+
+    pub fn get_potential_energy(&self, positions: &[Vec2]) -> f32 {
+        -self.amount * self.get_area(positions).ln()
+    }
+}
@@ -50,6 +50,13 @@ impl Segment {
         }
     }
 
+    // Position of the projection of a point along the segment,
+    // going from 0 on the first end to 1 on the second one
+    pub fn get_ratio(&self, point: &Vec2) -> f32 {
+        let direction = self.p2 - self.p1;
+        ((*point - self.p1).dot(direction) / direction.length_squared()).clamp(0., 1.)
+    }
+
     // Cast a vertical ray from the given point
     // Returns true if the ray passes through the segment
     pub fn vertical_raycast(&self, point: &Vec2) -> bool {
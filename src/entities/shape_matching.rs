@@ -0,0 +1,126 @@
+use super::dot::*;
+use macroquad::prelude::*;
+
+// Rest shape of the dots moved onto their current positions by the rotation and translation
+// fitting them best, weighted by the masses of the dots (Müller et al.)
+pub fn get_goal_positions(points: &[Dot], positions: &[Vec2]) -> Vec<Vec2> {
+    let weights = points
+        .iter()
+        .map(|point| {
+            if point.mass().is_finite() {
+                point.mass()
+            } else {
+                0.
+            }
+        })
+        .collect::<Vec<f32>>();
+    let total_weight = weights.iter().sum::<f32>();
+    if total_weight == 0. {
+        return positions.to_vec();
+    }
+
+    let center = weighted_mean(positions, &weights, total_weight);
+    let rest_center = weighted_mean(
+        &points
+            .iter()
+            .map(|point| point.rest_pos)
+            .collect::<Vec<Vec2>>(),
+        &weights,
+        total_weight,
+    );
+
+    // In 2D the rotation of the polar decomposition only depends on these two sums
+    let (cos_sum, sin_sum) = points.iter().zip(positions).zip(&weights).fold(
+        (0., 0.),
+        |(cos_sum, sin_sum), ((point, pos), weight)| {
+            let (rest, current) = (point.rest_pos - rest_center, *pos - center);
+            (
+                cos_sum + weight * rest.dot(current),
+                sin_sum + weight * rest.perp_dot(current),
+            )
+        },
+    );
+    let rotation = Vec2::new(cos_sum, sin_sum).normalize_or_zero();
+    if rotation == Vec2::ZERO {
+        return positions.to_vec();
+    }
+
+    points
+        .iter()
+        .map(|point| {
+            let rest = point.rest_pos - rest_center;
+            center
+                + vec2(
+                    rotation.x * rest.x - rotation.y * rest.y,
+                    rotation.y * rest.x + rotation.x * rest.y,
+                )
+        })
+        .collect()
+}
+
+fn weighted_mean(values: &[Vec2], weights: &[f32], total_weight: f32) -> Vec2 {
+    values
+        .iter()
+        .zip(weights)
+        .fold(vec2(0., 0.), |acc, (value, weight)| acc + *value * *weight)
+        / total_weight
+}
+
+// Stiffness pulling the dot, the one given being for a dot of the mass of the material
+fn get_dot_stiffness(point: &Dot, stiffness: f32, mass: f32) -> f32 {
+    if point.mass().is_finite() {
+        stiffness * point.mass() / mass
+    } else {
+        stiffness
+    }
+}
+
+// Forces pulling every dot towards its goal position
+pub fn get_shape_forces(
+    points: &[Dot],
+    positions: &[Vec2],
+    stiffness: f32,
+    mass: f32,
+) -> Vec<Vec2> {
+    get_goal_positions(points, positions)
+        .iter()
+        .zip(positions.iter().zip(points))
+        .map(|(goal, (pos, point))| (*goal - *pos) * get_dot_stiffness(point, stiffness, mass))
+        .collect()
+}
+
+pub fn get_shape_energy(points: &[Dot], positions: &[Vec2], stiffness: f32, mass: f32) -> f32 {
+    get_goal_positions(points, positions)
+        .iter()
+        .zip(positions.iter().zip(points))
+        .map(|(goal, (pos, point))| {
+            get_dot_stiffness(point, stiffness, mass) * (*goal - *pos).length_squared() / 2.
+        })
+        .sum()
+}
+
+// Moves the dots towards their goal positions as XPBD constraints softened by the compliance,
+// accumulating the Lagrange multipliers of the constraints
+pub fn solve_shape_constraints(
+    points: &[Dot],
+    positions: &mut [Vec2],
+    inverse_masses: &[f32],
+    lambdas: &mut [Vec2],
+    stiffness: f32,
+    mass: f32,
+    delta_t: f32,
+) {
+    let goals = get_goal_positions(points, positions);
+    positions
+        .iter_mut()
+        .zip(goals.into_iter().zip(points))
+        .zip(inverse_masses.iter().zip(lambdas.iter_mut()))
+        .filter(|(_, (inverse_mass, _))| **inverse_mass > 0.)
+        .for_each(|((pos, (goal, point)), (inverse_mass, lambda))| {
+            // The compliance is the inverse of the stiffness
+            let alpha = 1. / (get_dot_stiffness(point, stiffness, mass) * delta_t * delta_t);
+            let delta_lambda = (goal - *pos - *lambda * alpha) / (inverse_mass + alpha);
+            *pos += delta_lambda * *inverse_mass;
+            *lambda += delta_lambda;
+        });
+}
@@ -1,53 +1,91 @@
+use super::area::*;
 use super::dot::*;
 use super::interaction::*;
+use super::lattice::*;
+use super::material::{Material, Plasticity};
 use super::polygon::*;
-use crate::utils::math::close_to_equal;
-use crate::utils::math::runge_kutta_integration;
+use super::pressure::*;
+use super::segment::*;
+use super::shape_matching::*;
+use crate::utils::integrator::Integrator;
+use crate::utils::mesh::*;
 use macroquad::prelude::*;
+use std::f32::consts::PI;
 
-const DAMPING_FACTOR: f32 = 0.8;
-const STIFFNESS: f32 = 10.;
+// Acceleration of every dot, the presets being tuned against it
+pub const GRAVITY: f32 = 2.;
+pub const BALLOON_DOTS: usize = 24;
+pub const DEFAULT_WIDTH: f32 = 160.;
+pub const DEFAULT_HEIGHT: f32 = 120.;
+pub const CLOTH_COLOR: Color = Color::new(0.99, 0.98, 0., 0.4);
 
-fn is_dot_on_border(dot: &Dot, corner1: &Vec2, corner2: &Vec2) -> bool {
-    close_to_equal(dot.pos[0], corner1[0])
-        || close_to_equal(dot.pos[1], corner1[1])
-        || close_to_equal(dot.pos[0], corner2[0])
-        || close_to_equal(dot.pos[1], corner2[1])
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpringKind {
+    // Along the sides of the cells
+    Structural,
+    // Across the cells
+    Shear,
+    // Skipping a dot along the sides of the cells
+    Bend,
 }
 
-fn are_dots_on_border(dot1: &Dot, dot2: &Dot, corner1: &Vec2, corner2: &Vec2) -> bool {
-    is_dot_on_border(dot1, corner1, corner2)
-        && is_dot_on_border(dot2, corner1, corner2)
-        && (close_to_equal(dot1.pos[0], dot2.pos[0]) || close_to_equal(dot1.pos[1], dot2.pos[1]))
-}
 #[derive(Copy, Clone)]
 pub struct Spring {
-    stiffness: f32,
+    // None while the material has no springs of this kind, leaving the spring inactive
+    stiffness: Option<f32>,
+    // Share of the stiffness of the material the spring gets, making up for stretched cells
+    stiffness_scale: f32,
     damping_factor: f32,
     rest_length: f32,
+    original_length: f32,
     pub index_1: usize,
     pub index_2: usize,
+    pub kind: SpringKind,
     pub is_on_border: bool,
 }
 
 impl Spring {
     pub fn new(
         dots: &[Dot],
-        index_1: usize,
-        index_2: usize,
-        stiffness: f32,
+        (index_1, index_2): (usize, usize),
+        material: &Material,
+        kind: SpringKind,
         is_on_border: bool,
     ) -> Spring {
+        let rest_length = (dots[index_1].pos - dots[index_2].pos).length();
         Spring {
             index_1,
             index_2,
-            rest_length: (dots[index_1].pos - dots[index_2].pos).length(),
-            stiffness,
-            damping_factor: DAMPING_FACTOR,
+            rest_length,
+            original_length: rest_length,
+            stiffness: material.stiffness_of(kind, is_on_border),
+            stiffness_scale: 1.,
+            damping_factor: material.damping,
+            kind,
             is_on_border,
         }
     }
 
+    pub fn set_material(&mut self, material: &Material) {
+        self.stiffness = material
+            .stiffness_of(self.kind, self.is_on_border)
+            .map(|stiffness| stiffness * self.stiffness_scale);
+        self.damping_factor = material.damping;
+    }
+
+    pub fn stiffness_scale(&self) -> f32 {
+        self.stiffness_scale
+    }
+
+    pub fn set_stiffness_scale(&mut self, scale: f32, material: &Material) {
+        self.stiffness_scale = scale;
+        self.set_material(material);
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.stiffness.is_some()
+    }
+
     pub fn draw(&self, dot1: &Dot, dot2: &Dot, color: Option<Color>) {
         draw_line(
             dot1.pos[0],
@@ -60,162 +98,365 @@ impl Spring {
     }
 
     fn get_hooke_force(&self, pos1: Vec2, pos2: Vec2) -> f32 {
-        ((pos1 - pos2).length() - self.rest_length) * self.stiffness
+        ((pos1 - pos2).length() - self.rest_length) * self.stiffness.unwrap_or(0.)
     }
 
     fn get_damping(&self, pos1: Vec2, pos2: Vec2, vel1: Vec2, vel2: Vec2) -> f32 {
         (pos1 - pos2).normalize().dot(vel1 - vel2) * self.damping_factor
     }
 
+    // Moves the ends of the spring towards its rest length, softened by the compliance
+    // as in XPBD, accumulating the Lagrange multiplier of the constraint
+    pub fn solve_distance_constraint(
+        &self,
+        positions: &mut [Vec2],
+        inverse_masses: &[f32],
+        lambda: &mut f32,
+        alpha: f32,
+    ) {
+        let (w1, w2) = (inverse_masses[self.index_1], inverse_masses[self.index_2]);
+        let distance = positions[self.index_1] - positions[self.index_2];
+        if w1 + w2 + alpha == 0. || distance.length() == 0. {
+            return;
+        }
+
+        let constraint = distance.length() - self.rest_length;
+        let delta_lambda = (-constraint - alpha * *lambda) / (w1 + w2 + alpha);
+        let correction = distance.normalize() * delta_lambda;
+        positions[self.index_1] += correction * w1;
+        positions[self.index_2] -= correction * w2;
+        *lambda += delta_lambda;
+    }
+
+    // Moves the rest length towards the current one when stretched or squeezed beyond the yield,
+    // never further from the original length than the maximum deformation
+    pub fn deform(&mut self, pos1: Vec2, pos2: Vec2, plasticity: &Plasticity, delta_t: f32) {
+        let elongation = (pos1 - pos2).length() - self.rest_length;
+        let yield_elongation = plasticity.yield_ratio * self.rest_length;
+        if elongation.abs() <= yield_elongation {
+            return;
+        }
+
+        let plastic_elongation = elongation - yield_elongation * elongation.signum();
+        self.rest_length = (self.rest_length + plastic_elongation * plasticity.creep * delta_t)
+            .clamp(
+                self.original_length * (1. - plasticity.max_deformation),
+                self.original_length * (1. + plasticity.max_deformation),
+            );
+    }
+
+    pub fn get_strain(&self, pos1: Vec2, pos2: Vec2) -> f32 {
+        ((pos1 - pos2).length() - self.rest_length) / self.rest_length
+    }
+
+    pub fn get_potential_energy(&self, pos1: Vec2, pos2: Vec2) -> f32 {
+        self.stiffness.unwrap_or(0.) * ((pos1 - pos2).length() - self.rest_length).powi(2) / 2.
+    }
+
     pub fn get_force(&self, pos1: Vec2, pos2: Vec2, vel1: Vec2, vel2: Vec2) -> Vec2 {
         (self.get_hooke_force(pos1, pos2) + self.get_damping(pos1, pos2, vel1, vel2))
             * ((pos1 - pos2).normalize())
     }
 }
 
-fn get_subdivisions(
-    hor_distance: f32,
-    ver_distance: f32,
-    hor_sub: Option<i32>,
-    ver_sub: Option<i32>,
-) -> (i32, i32) {
-    let temp_hor_sub = hor_sub.unwrap_or(1);
-    let temp_ver_sub = ver_sub.unwrap_or(1);
-    if (hor_distance / ver_distance).abs() < 1.1 && (hor_distance / ver_distance).abs() > 0.9 {
-        (temp_hor_sub, temp_ver_sub)
-    } else if hor_distance > ver_distance {
-        get_subdivisions(
-            (hor_distance * temp_hor_sub as f32) / (temp_hor_sub as f32 + 1.),
-            ver_distance,
-            Some(temp_hor_sub + 1),
-            ver_sub,
-        )
-    } else {
-        get_subdivisions(
-            hor_distance,
-            (ver_distance * temp_ver_sub as f32) / (temp_ver_sub as f32 + 1.),
-            hor_sub,
-            Some(temp_ver_sub + 1),
-        )
-    }
-}
-
-fn generate_dots(top_left_corner: Vec2, bottom_right_corner: Vec2) -> (Vec<Dot>, (f32, f32)) {
-    // Get subdivisions
-    let (horizontal_subdivisions, vertical_subdivisions) = get_subdivisions(
-        bottom_right_corner[0] - top_left_corner[0],
-        bottom_right_corner[1] - top_left_corner[1],
-        None,
-        None,
-    );
-    let mut dots: Vec<Dot> = vec![];
-    let horizontal_step =
-        (bottom_right_corner[0] - top_left_corner[0]) / (horizontal_subdivisions as f32);
-    let vertical_step =
-        (bottom_right_corner[1] - top_left_corner[1]) / (vertical_subdivisions as f32);
-    for horizontal_pos in 0..horizontal_subdivisions + 1 {
-        for vertical_pos in 0..vertical_subdivisions + 1 {
-            dots.push(Dot::new(Some(
-                top_left_corner
-                    + vec2(
-                        (horizontal_pos as f32) * horizontal_step,
-                        (vertical_pos as f32) * vertical_step,
-                    ),
-            )));
-        }
-    }
-    (dots, (horizontal_step, vertical_step))
+// Dots on the border of a round body of the given perimeter, about a cell apart
+fn default_segments(perimeter: f32) -> usize {
+    ((perimeter / DEFAULT_SPACING).round() as usize).max(6)
 }
 
-// Generates the spring using dots proximity
-fn generate_springs(
-    dots: &[Dot],
-    mut horizontal_distance: f32,
-    mut vertical_distance: f32,
-    (corner1, corner2): (&Vec2, &Vec2),
-) -> Vec<Spring> {
-    // Rounding position to account of f32 approximations
-    fn round(number_to_round: f32) -> f32 {
-        let accuracy = 1000.;
-        (number_to_round * accuracy).round() / accuracy
-    }
-    horizontal_distance = round(horizontal_distance);
-    vertical_distance = round(vertical_distance);
-
-    // This is horrible
-    dots.iter()
-        .enumerate()
-        .flat_map(|(index, dot)| {
-            dots.iter()
-                .enumerate()
-                .map(|(inner_index, _)| inner_index)
-                .filter(|&inner_index| {
-                    let distance = (dots[inner_index].pos - dot.pos).abs();
-                    let (distance_x, distance_y) = (round(distance[0]), round(distance[1]));
-                    distance.length() > 0.
-                        && ((close_to_equal(distance_x, 0.)
-                            && close_to_equal(distance_y, vertical_distance))
-                            || (close_to_equal(distance[1], 0.)
-                                && close_to_equal(distance_x, horizontal_distance))
-                            || (close_to_equal(distance_x, horizontal_distance)
-                                && close_to_equal(distance_y, vertical_distance)))
-                })
-                .map(|inner_index| {
-                    Spring::new(
-                        dots,
-                        index,
-                        inner_index,
-                        STIFFNESS
-                            + if are_dots_on_border(
-                                &dots[index],
-                                &dots[inner_index],
-                                corner1,
-                                corner2,
-                            ) {
-                                2.
-                            } else {
-                                0.
-                            },
-                        are_dots_on_border(&dots[index], &dots[inner_index], corner1, corner2),
-                    )
-                })
-                .collect::<Vec<Spring>>()
-        })
-        .collect::<Vec<Spring>>()
+// Rings of dots across the given thickness, about a cell apart
+fn default_rings(thickness: f32) -> usize {
+    ((thickness / DEFAULT_SPACING).round() as usize).max(1)
 }
 
 pub struct SoftBody {
     pub points: Vec<Dot>,
     pub springs: Vec<Spring>,
+    pub triangles: Vec<Triangle>,
+    // Cells drawn filled, for the bodies without a closed border
+    pub quads: Vec<[usize; 4]>,
+    pub surface: Surface,
+    material: Material,
+    pub pressure: Option<Pressure>,
 }
 
 impl SoftBody {
-    pub fn new(pos1: f32, pos2: f32) -> SoftBody {
-        let (corner1, corner2) = (vec2(pos1 - 80., pos2 - 100.), vec2(pos1 + 80., pos2 + 20.));
-        let (dots, (horizontal_step, vertical_step)) = generate_dots(corner1, corner2);
+    // Rectangular body centered on the position
+    pub fn new(
+        pos1: f32,
+        pos2: f32,
+        size: Option<(f32, f32)>,
+        resolution: Option<Resolution>,
+        lattice: Option<Lattice>,
+        material: Option<Material>,
+    ) -> SoftBody {
+        let material = material.unwrap_or_default();
+        let (width, height) = size.unwrap_or((DEFAULT_WIDTH, DEFAULT_HEIGHT));
+        let half_size = vec2(width, height) / 2.;
+        let (mut dots, springs, triangles) = generate_lattice(
+            lattice.unwrap_or_default(),
+            vec2(pos1, pos2) - half_size,
+            vec2(pos1, pos2) + half_size,
+            resolution.unwrap_or_default(),
+            &material,
+        );
+        dots.iter_mut().for_each(|dot| {
+            dot.radius = material.radius;
+        });
+        let positions = dots.iter().map(|dot| dot.pos).collect::<Vec<Vec2>>();
+        SoftBody {
+            triangles: triangles
+                .into_iter()
+                .map(|indices| Triangle::new(&positions, indices))
+                .collect(),
+            quads: vec![],
+            springs,
+            points: dots,
+            surface: Surface::default(),
+            material,
+            pressure: None,
+        }
+    }
+
+    // Sheet of dots centered on the position, optionally hanging from its top row.
+    // It has no border for the other bodies to bump into, nor cells keeping their area
+    pub fn cloth(
+        pos1: f32,
+        pos2: f32,
+        size: Option<(f32, f32)>,
+        resolution: Option<Resolution>,
+        pinned: bool,
+        material: Option<Material>,
+    ) -> SoftBody {
+        let material = material.unwrap_or_else(Material::cloth);
+        let (width, height) = size.unwrap_or((DEFAULT_WIDTH, DEFAULT_HEIGHT));
+        let half_size = vec2(width, height) / 2.;
+        let (mut dots, springs, quads, top_row) = generate_grid(
+            vec2(pos1, pos2) - half_size,
+            vec2(pos1, pos2) + half_size,
+            resolution.unwrap_or_default(),
+            &material,
+        );
+        dots.iter_mut().for_each(|dot| dot.radius = material.radius);
+        if pinned {
+            top_row.iter().for_each(|&index| dots[index].pin(None));
+        }
         SoftBody {
-            springs: generate_springs(
-                &dots,
-                horizontal_step.abs(),
-                vertical_step.abs(),
-                (&corner1, &corner2),
-            ),
+            triangles: vec![],
+            quads,
+            springs,
             points: dots,
+            surface: Surface::default(),
+            material,
+            pressure: None,
         }
     }
 
+    // Ring of dots held by border springs and inflated by the gas inside
+    pub fn new_balloon(
+        pos1: f32,
+        pos2: f32,
+        radius: f32,
+        dots_count: Option<usize>,
+        pressure: Option<f32>,
+        material: Option<Material>,
+    ) -> SoftBody {
+        let material = material.unwrap_or_default();
+        let dots_count = dots_count.unwrap_or(BALLOON_DOTS);
+        let mut dots = (0..dots_count)
+            .map(|index| {
+                let angle = 2. * PI * index as f32 / dots_count as f32;
+                let mut dot = Dot::new(vec2(pos1, pos2) + vec2(angle.cos(), angle.sin()) * radius);
+                dot.radius = material.radius;
+                dot
+            })
+            .collect::<Vec<Dot>>();
+        // As heavy as a body filling the ring
+        weigh_dots(
+            &mut dots,
+            &[(0..dots_count).collect::<Vec<usize>>()],
+            &material,
+        );
+        let springs = (0..dots_count)
+            .map(|index| {
+                Spring::new(
+                    &dots,
+                    (index, (index + 1) % dots_count),
+                    &material,
+                    SpringKind::Structural,
+                    true,
+                )
+            })
+            .collect::<Vec<Spring>>();
+        let positions = dots.iter().map(|dot| dot.pos).collect::<Vec<Vec2>>();
+
+        SoftBody {
+            pressure: Some(Pressure::new(
+                (0..dots_count).collect(),
+                &positions,
+                pressure,
+            )),
+            points: dots,
+            springs,
+            triangles: vec![],
+            quads: vec![],
+            surface: Surface::default(),
+            material,
+        }
+    }
+
+    // Body made of dots at the positions, connected by the sides of the triangles
+    fn from_mesh(positions: &[Vec2], triangles: Vec<[usize; 3]>, material: Material) -> SoftBody {
+        let mut dots = positions
+            .iter()
+            .map(|pos| {
+                let mut dot = Dot::new(*pos);
+                dot.radius = material.radius;
+                dot
+            })
+            .collect::<Vec<Dot>>();
+        weigh_dots(&mut dots, &triangles, &material);
+        let springs = get_edges(&triangles)
+            .into_iter()
+            .map(|(ends, is_on_border)| {
+                Spring::new(&dots, ends, &material, SpringKind::Structural, is_on_border)
+            })
+            .collect::<Vec<Spring>>();
+
+        SoftBody {
+            triangles: triangles
+                .into_iter()
+                .map(|indices| Triangle::new(positions, indices))
+                .collect(),
+            quads: vec![],
+            springs,
+            points: dots,
+            surface: Surface::default(),
+            material,
+            pressure: None,
+        }
+    }
+
+    // Body filling a closed outline with dots about the spacing apart, connected by the sides
+    // of the triangles covering it. None when the outline encloses no triangle, as when its
+    // points are all on a line
+    pub fn from_outline(
+        outline: &[Vec2],
+        spacing: Option<f32>,
+        material: Option<Material>,
+    ) -> Option<SoftBody> {
+        let (positions, triangles) = mesh_outline(outline, spacing.unwrap_or(DEFAULT_SPACING));
+        if triangles.is_empty() {
+            return None;
+        }
+        Some(SoftBody::from_mesh(
+            &positions,
+            triangles,
+            material.unwrap_or_default(),
+        ))
+    }
+
+    // Disk made of rings of dots around the center, with the given number of dots on its border
+    pub fn circle(
+        center: Vec2,
+        radius: f32,
+        segments: Option<usize>,
+        rings: Option<usize>,
+        material: Option<Material>,
+    ) -> SoftBody {
+        SoftBody::ring(center, radius, 0., segments, rings, material)
+    }
+
+    pub fn ring(
+        center: Vec2,
+        radius: f32,
+        inner_radius: f32,
+        segments: Option<usize>,
+        rings: Option<usize>,
+        material: Option<Material>,
+    ) -> SoftBody {
+        let (positions, triangles) = radial_mesh(
+            center,
+            center,
+            inner_radius,
+            radius,
+            segments.unwrap_or_else(|| default_segments(2. * PI * radius)),
+            rings.unwrap_or_else(|| default_rings(radius - inner_radius)),
+        );
+        SoftBody::from_mesh(&positions, triangles, material.unwrap_or_default())
+    }
+
+    // Horizontal body with round ends, the length being the one of its straight sides
+    pub fn capsule(
+        center: Vec2,
+        length: f32,
+        radius: f32,
+        segments: Option<usize>,
+        rings: Option<usize>,
+        material: Option<Material>,
+    ) -> SoftBody {
+        let (positions, triangles) = radial_mesh(
+            center - vec2(length / 2., 0.),
+            center + vec2(length / 2., 0.),
+            0.,
+            radius,
+            segments.unwrap_or_else(|| default_segments(2. * length + 2. * PI * radius)),
+            rings.unwrap_or_else(|| default_rings(radius)),
+        );
+        SoftBody::from_mesh(&positions, triangles, material.unwrap_or_default())
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    // Changes the material of the body, keeping the rest shape of the springs
+    // and how the mass is spread over the dots. The kinds of springs it lacks are kept
+    // inactive, so another material can bring them back
+    pub fn set_material(&mut self, material: Material) {
+        let mass_ratio = material.mass / self.material.mass;
+        self.springs
+            .iter_mut()
+            .for_each(|spring| spring.set_material(&material));
+        self.points.iter_mut().for_each(|point| {
+            point.radius = material.radius;
+            point.set_mass(point.mass() * mass_ratio);
+        });
+        self.material = material;
+    }
+
+    pub fn is_cloth(&self) -> bool {
+        !self.quads.is_empty()
+    }
+
+    // Scales the mass of every dot, its share of the body, by the density at its position
+    pub fn set_density(&mut self, density: &dyn Fn(Vec2) -> f32) {
+        self.points
+            .iter_mut()
+            .filter(|point| !point.is_frozen())
+            .for_each(|point| point.set_mass(point.mass() * density(point.pos)));
+    }
+
     pub fn draw(&self) {
         self.points.iter().for_each(|point| point.draw());
-        self.springs.iter().for_each(|spring| {
-            spring.draw(
-                &self.points[spring.index_1],
-                &self.points[spring.index_2],
-                None,
-            )
-        });
+        self.springs
+            .iter()
+            .filter(|spring| spring.is_active())
+            .for_each(|spring| {
+                spring.draw(
+                    &self.points[spring.index_1],
+                    &self.points[spring.index_2],
+                    None,
+                )
+            });
     }
 
     pub fn draw_border(&self) {
+        self.quads.iter().for_each(|&[a, b, c, d]| {
+            let [a, b, c, d] = [a, b, c, d].map(|index| self.points[index].pos);
+            draw_triangle(a, b, c, CLOTH_COLOR);
+            draw_triangle(a, c, d, CLOTH_COLOR);
+        });
         self.springs.iter().for_each(|spring| {
             if spring.is_on_border {
                 spring.draw(
@@ -225,52 +466,454 @@ impl SoftBody {
                 )
             }
         });
+        self.points
+            .iter()
+            .filter(|point| point.is_pinned())
+            .for_each(|point| point.draw());
     }
 
-    pub fn update_runge_kutta(&mut self) {
-        self.springs.clone().iter().for_each(|spring| {
-            if self.points[spring.index_1].pos != self.points[spring.index_2].pos {
-                self.update_masses_acceleration(spring);
-            }
-        });
-        self.points.iter_mut().for_each(|point| point.update());
+    pub fn closest_dot(&self, point: &Vec2) -> Option<usize> {
+        self.points
+            .iter()
+            .enumerate()
+            .min_by(|(_, dot_1), (_, dot_2)| {
+                (dot_1.pos - *point)
+                    .length()
+                    .total_cmp(&(dot_2.pos - *point).length())
+            })
+            .map(|(index, _)| index)
+    }
+
+    // Index of the closest dot covering the point
+    pub fn dot_at(&self, point: &Vec2) -> Option<usize> {
+        self.closest_dot(point)
+            .filter(|&index| (self.points[index].pos - *point).length() < self.points[index].radius)
+    }
+
+    pub fn border_springs(&self) -> Vec<Spring> {
+        self.springs
+            .iter()
+            .filter(|spring| spring.is_on_border)
+            .copied()
+            .collect()
+    }
+
+    pub fn border_segments(&self) -> Vec<Segment> {
+        self.border_springs()
+            .iter()
+            .map(|spring| Segment {
+                p1: self.points[spring.index_1].pos,
+                p2: self.points[spring.index_2].pos,
+            })
+            .collect()
+    }
+
+    pub fn is_point_inside(&self, point: &Vec2) -> bool {
+        let counter = self
+            .border_segments()
+            .iter()
+            .fold(0, |acc, cur| acc + cur.vertical_raycast(point) as i32);
+
+        counter % 2 != 0
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::new(
+            &self
+                .points
+                .iter()
+                .map(|point| (point.pos[0], point.pos[1]))
+                .collect::<Vec<(f32, f32)>>(),
+        )
+    }
+
+    pub fn is_out_of_bounds(&self, bounds: &BoundingBox) -> bool {
+        self.points
+            .iter()
+            .all(|point| point.is_out_of_bounds(bounds))
+    }
+
+    pub fn update(&mut self, integrator: &dyn Integrator, gravity: Vec2, delta_t: f32) {
+        let positions = self
+            .points
+            .iter()
+            .map(|point| point.pos)
+            .collect::<Vec<Vec2>>();
+        let velocities = self
+            .points
+            .iter()
+            .map(|point| point.vel)
+            .collect::<Vec<Vec2>>();
+
+        let (positions, velocities) = integrator.integrate(
+            &|positions, velocities| self.get_accelerations(positions, velocities, gravity),
+            &positions,
+            &velocities,
+            delta_t,
+        );
+
+        self.points
+            .iter_mut()
+            .zip(positions.into_iter().zip(velocities))
+            .for_each(|(point, (pos, vel))| point.update(pos, vel, self.material.max_speed));
         self.springs.iter().for_each(|spring| {
             handle_point_point_collision(&mut self.points, spring.index_1, spring.index_2);
         });
     }
 
-    fn update_masses_acceleration(&mut self, spring: &Spring) {
-        let get_acceleration = move |position_1: Vec2,
-                                     position_2: Vec2,
-                                     velocity_1: Vec2,
-                                     velocity_2: Vec2|
-              -> (Vec2, Vec2) {
-            let spring_force = spring.get_force(position_1, position_2, velocity_1, velocity_2);
+    // Number of smaller steps the step is split into for the integration of the springs to stay
+    // stable, the body oscillating the faster as its springs are stiffer and its dots lighter
+    pub fn get_substeps(&self, integrator: &dyn Integrator, delta_t: f32) -> usize {
+        let mut rates = vec![(0., 0.); self.points.len()];
+        self.springs
+            .iter()
+            .filter(|spring| spring.is_active())
+            .for_each(|spring| {
+                [spring.index_1, spring.index_2].iter().for_each(|&index| {
+                    rates[index].0 += spring.stiffness.unwrap_or(0.);
+                    rates[index].1 += spring.damping_factor;
+                })
+            });
+        let shape_rate = self.material.shape_matching.unwrap_or(0.) / self.material.mass;
+        let fastest = self
+            .points
+            .iter()
+            .zip(rates)
+            .map(|(point, (stiffness, damping))| {
+                let inverse_mass = point.inverse_mass();
+                (stiffness * inverse_mass + shape_rate).sqrt() + damping * inverse_mass / 2.
+            })
+            .fold(0., f32::max);
+        ((fastest * delta_t / integrator.stable_step()).ceil() as usize).max(1)
+    }
+
+    // Acceleration of every dot given by the springs, the gravity and the external
+    // accelerations accumulated on the dots, for a state of the whole body
+    pub fn get_accelerations(
+        &self,
+        positions: &[Vec2],
+        velocities: &[Vec2],
+        gravity: Vec2,
+    ) -> Vec<Vec2> {
+        let mut accelerations = self
+            .points
+            .iter()
+            .map(|point| {
+                // Frozen dots keep their velocity, pinned ones going straight to their target
+                if point.is_frozen() {
+                    vec2(0., 0.)
+                } else {
+                    point.acceleration + gravity
+                }
+            })
+            .collect::<Vec<Vec2>>();
+
+        self.springs
+            .iter()
+            .filter(|spring| {
+                spring.is_active() && positions[spring.index_1] != positions[spring.index_2]
+            })
+            .for_each(|spring| {
+                let spring_force = spring.get_force(
+                    positions[spring.index_1],
+                    positions[spring.index_2],
+                    velocities[spring.index_1],
+                    velocities[spring.index_2],
+                );
+                accelerations[spring.index_1] -=
+                    spring_force * self.points[spring.index_1].inverse_mass();
+                accelerations[spring.index_2] +=
+                    spring_force * self.points[spring.index_2].inverse_mass();
+            });
+
+        if let Some(bulk_modulus) = self.material.bulk_modulus {
+            let mut forces = vec![vec2(0., 0.); positions.len()];
+            self.triangles
+                .iter()
+                .for_each(|triangle| triangle.add_forces(positions, bulk_modulus, &mut forces));
+            forces
+                .iter()
+                .zip(self.points.iter().zip(accelerations.iter_mut()))
+                .for_each(|(force, (point, acceleration))| {
+                    *acceleration += *force * point.inverse_mass()
+                });
+        }
+
+        if let Some(stiffness) = self.material.shape_matching {
+            get_shape_forces(&self.points, positions, stiffness, self.material.mass)
+                .iter()
+                .zip(self.points.iter().zip(accelerations.iter_mut()))
+                .for_each(|(force, (point, acceleration))| {
+                    *acceleration += *force * point.inverse_mass()
+                });
+        }
 
-            (
-                -spring_force + vec2(0., 0.68),
-                spring_force + vec2(0., 0.68),
-            )
+        if let Some(pressure) = &self.pressure {
+            pressure
+                .get_forces(positions)
+                .iter()
+                .zip(self.points.iter().zip(accelerations.iter_mut()))
+                .for_each(|(force, (point, acceleration))| {
+                    *acceleration += *force * point.inverse_mass()
+                });
+        }
+
+        self.points
+            .iter()
+            .zip(accelerations.iter_mut())
+            .filter(|(point, _)| point.is_frozen())
+            .for_each(|(_, acceleration)| *acceleration = vec2(0., 0.));
+
+        accelerations
+    }
+
+    // Kinetic, elastic, gravitational and gas energy of the body, immovable dots having none,
+    // the pull towards the rest shape and the resistance of the cells counting as elastic
+    pub fn get_energy(&self, gravity: Vec2) -> f32 {
+        let kinetic = self
+            .points
+            .iter()
+            .filter(|point| !point.is_frozen())
+            .map(|point| point.mass() * point.vel.length_squared() / 2.)
+            .sum::<f32>();
+        let elastic = self
+            .springs
+            .iter()
+            .map(|spring| {
+                spring.get_potential_energy(
+                    self.points[spring.index_1].pos,
+                    self.points[spring.index_2].pos,
+                )
+            })
+            .sum::<f32>();
+        let gravitational = self
+            .points
+            .iter()
+            .filter(|point| !point.is_frozen())
+            .map(|point| -point.mass() * point.pos.dot(gravity))
+            .sum::<f32>();
+
+        let positions = self
+            .points
+            .iter()
+            .map(|point| point.pos)
+            .collect::<Vec<Vec2>>();
+        let shape = self.material.shape_matching.map_or(0., |stiffness| {
+            get_shape_energy(&self.points, &positions, stiffness, self.material.mass)
+        });
+        let cells = self.material.bulk_modulus.map_or(0., |bulk_modulus| {
+            self.triangles
+                .iter()
+                .map(|triangle| triangle.get_potential_energy(&positions, bulk_modulus))
+                .sum()
+        });
+        let gas = self
+            .pressure
+            .as_ref()
+            .map_or(0., |pressure| pressure.get_potential_energy(&positions));
+
+        kinetic + elastic + cells + shape + gravitational + gas
+    }
+
+    // Lets the springs of a plastic body keep part of their deformation
+    pub fn deform(&mut self, delta_t: f32) {
+        if let Some(plasticity) = self.material.plasticity {
+            let points = &self.points;
+            self.springs
+                .iter_mut()
+                .filter(|spring| spring.is_active())
+                .for_each(|spring| {
+                    spring.deform(
+                        points[spring.index_1].pos,
+                        points[spring.index_2].pos,
+                        &plasticity,
+                        delta_t,
+                    )
+                });
+        }
+    }
+
+    // Breaks the springs strained beyond the limit of the material, returning whether any broke
+    pub fn tear(&mut self) -> bool {
+        let tear_strain = match self.material.tear_strain {
+            Some(tear_strain) => tear_strain,
+            None => return false,
         };
+        let springs_count = self.springs.len();
+        let points = &self.points;
+        self.springs.retain(|spring| {
+            !spring.is_active()
+                || spring
+                    .get_strain(points[spring.index_1].pos, points[spring.index_2].pos)
+                    .abs()
+                    <= tear_strain
+        });
+        if self.springs.len() == springs_count {
+            return false;
+        }
 
-        let point1 = self.points[spring.index_1];
-        let point2 = self.points[spring.index_2];
+        let springs = &self.springs;
+        let is_connected = |index_1: usize, index_2: usize| {
+            springs.iter().any(|spring| {
+                (spring.index_1, spring.index_2) == (index_1, index_2)
+                    || (spring.index_1, spring.index_2) == (index_2, index_1)
+            })
+        };
+        self.triangles.retain(|triangle| {
+            let [a, b, c] = triangle.indices;
+            is_connected(a, b) && is_connected(b, c) && is_connected(c, a)
+        });
+        self.quads.retain(|&[a, b, c, d]| {
+            is_connected(a, b) && is_connected(b, c) && is_connected(c, d) && is_connected(d, a)
+        });
 
-        let (push_vec_1, push_vec_2) = runge_kutta_integration(
-            &get_acceleration,
-            point1.pos,
-            point2.pos,
-            point1.vel,
-            point2.vel,
-        );
+        // The gas escapes through a hole in the ring
+        let is_ring_broken = self.pressure.as_ref().is_some_and(|pressure| {
+            pressure
+                .ring
+                .iter()
+                .zip(pressure.ring.iter().cycle().skip(1))
+                .any(|(&index_1, &index_2)| !is_connected(index_1, index_2))
+        });
+        if is_ring_broken {
+            self.pressure = None;
+        }
+
+        self.update_border();
+        true
+    }
+
+    // A spring is on the border when the cells around it are all on the same side of it,
+    // the springs outside any cell keeping their place
+    fn update_border(&mut self) {
+        let SoftBody {
+            points,
+            springs,
+            triangles,
+            material,
+            ..
+        } = self;
+        springs
+            .iter_mut()
+            .filter(|spring| spring.kind != SpringKind::Bend)
+            .for_each(|spring| {
+                let (start, end) = (
+                    points[spring.index_1].rest_pos,
+                    points[spring.index_2].rest_pos,
+                );
+                let sides = triangles
+                    .iter()
+                    .filter(|triangle| triangle.has_side(spring.index_1, spring.index_2))
+                    .flat_map(|triangle| triangle.indices)
+                    .map(|index| (end - start).perp_dot(points[index].rest_pos - start))
+                    .filter(|side| *side != 0.)
+                    .collect::<Vec<f32>>();
+                if !sides.is_empty() {
+                    spring.is_on_border =
+                        sides.iter().all(|side| *side > 0.) || sides.iter().all(|side| *side < 0.);
+                    spring.set_material(material);
+                }
+            });
+    }
+
+    // Keeps the largest group of dots still connected by springs, returning the other groups
+    // as new bodies, if the body fell apart. Dots left without any spring are lost as dust,
+    // and the inactive springs hold nothing together
+    pub fn split(&mut self) -> Option<Vec<SoftBody>> {
+        let mut component_of = vec![None; self.points.len()];
+        let mut components: Vec<Vec<usize>> = vec![];
+        (0..self.points.len()).for_each(|start| {
+            if component_of[start].is_some() {
+                return;
+            }
+            let mut component = vec![start];
+            component_of[start] = Some(components.len());
+            let mut next = 0;
+            while next < component.len() {
+                let index = component[next];
+                self.springs
+                    .iter()
+                    .filter(|spring| spring.is_active())
+                    .filter_map(|spring| {
+                        if spring.index_1 == index {
+                            Some(spring.index_2)
+                        } else if spring.index_2 == index {
+                            Some(spring.index_1)
+                        } else {
+                            None
+                        }
+                    })
+                    .for_each(|neighbour| {
+                        if component_of[neighbour].is_none() {
+                            component_of[neighbour] = Some(components.len());
+                            component.push(neighbour);
+                        }
+                    });
+                next += 1;
+            }
+            components.push(component);
+        });
+        if components.len() == 1 {
+            return None;
+        }
+
+        components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+        let mut bodies = components
+            .iter()
+            .enumerate()
+            .filter(|(rank, component)| *rank == 0 || component.len() > 1)
+            .map(|(_, component)| self.extract(component))
+            .collect::<Vec<SoftBody>>();
+        *self = bodies.remove(0);
+        Some(bodies)
+    }
+
+    // Body made of some of the dots, with the springs and cells between them
+    fn extract(&self, component: &[usize]) -> SoftBody {
+        let mut new_index = vec![None; self.points.len()];
+        component
+            .iter()
+            .enumerate()
+            .for_each(|(index, &old_index)| new_index[old_index] = Some(index));
 
-        self.points[spring.index_1].add_acceleration(push_vec_1);
-        self.points[spring.index_2].add_acceleration(push_vec_2);
+        SoftBody {
+            points: component.iter().map(|&index| self.points[index]).collect(),
+            springs: self
+                .springs
+                .iter()
+                .filter_map(|spring| {
+                    Some(Spring {
+                        index_1: new_index[spring.index_1]?,
+                        index_2: new_index[spring.index_2]?,
+                        ..*spring
+                    })
+                })
+                .collect(),
+            triangles: self
+                .triangles
+                .iter()
+                .filter_map(|triangle| {
+                    let [a, b, c] = triangle.indices;
+                    Some(triangle.with_indices([new_index[a]?, new_index[b]?, new_index[c]?]))
+                })
+                .collect(),
+            quads: self
+                .quads
+                .iter()
+                .filter_map(|&[a, b, c, d]| {
+                    Some([new_index[a]?, new_index[b]?, new_index[c]?, new_index[d]?])
+                })
+                .collect(),
+            surface: self.surface,
+            material: self.material,
+            pressure: None,
+        }
     }
 
     pub fn handle_collision(&mut self, polygon: &Polygon) {
+        let surface = self.surface.combine(&polygon.surface);
         self.points
             .iter_mut()
-            .for_each(|point| handle_point_polygon_collision(point, polygon));
+            .for_each(|point| handle_point_polygon_collision(point, polygon, &surface));
     }
 }
@@ -0,0 +1,191 @@
+use super::interaction::*;
+use super::polygon::*;
+use super::shape_matching::*;
+use super::soft_body::*;
+use macroquad::prelude::*;
+
+pub const DEFAULT_ITERATIONS: usize = 10;
+pub const DEFAULT_COMPLIANCE: f32 = 0.001;
+
+#[derive(Copy, Clone, Debug)]
+pub struct XpbdSettings {
+    pub iterations: usize,
+    // Inverse of the stiffness of the springs, zero meaning rigid
+    pub compliance: f32,
+}
+
+impl Default for XpbdSettings {
+    fn default() -> Self {
+        Self {
+            iterations: DEFAULT_ITERATIONS,
+            compliance: DEFAULT_COMPLIANCE,
+        }
+    }
+}
+
+struct Contact {
+    index: usize,
+    normal: Vec2,
+    depth: f32,
+    approaching_speed: f32,
+    surface: Surface,
+}
+
+// Moves the body by solving its springs and the contacts with the polygons
+// as position constraints, then deriving the velocities from the displacement
+pub fn update_xpbd(
+    body: &mut SoftBody,
+    polygons: &[Polygon],
+    settings: &XpbdSettings,
+    gravity: Vec2,
+    delta_t: f32,
+) {
+    let inverse_masses = body
+        .points
+        .iter()
+        .map(|point| point.inverse_mass())
+        .collect::<Vec<f32>>();
+
+    // Predicting the positions with the external accelerations and the gas only
+    let pressure_forces = match &body.pressure {
+        Some(pressure) => pressure.get_forces(
+            &body
+                .points
+                .iter()
+                .map(|point| point.pos)
+                .collect::<Vec<Vec2>>(),
+        ),
+        None => vec![vec2(0., 0.); body.points.len()],
+    };
+    let velocities = body
+        .points
+        .iter()
+        .zip(inverse_masses.iter().zip(&pressure_forces))
+        .map(|(point, (inverse_mass, pressure_force))| {
+            if *inverse_mass > 0. {
+                point.vel
+                    + (point.acceleration + gravity + *pressure_force * *inverse_mass) * delta_t
+            } else {
+                point.vel
+            }
+        })
+        .collect::<Vec<Vec2>>();
+    let mut positions = body
+        .points
+        .iter()
+        .zip(&velocities)
+        .map(|(point, vel)| point.pos + *vel * delta_t)
+        .collect::<Vec<Vec2>>();
+
+    let alpha = settings.compliance / (delta_t * delta_t);
+    let mut lambdas = vec![0.; body.springs.len()];
+    let mut area_lambdas = vec![0.; body.triangles.len()];
+    let mut shape_lambdas = vec![vec2(0., 0.); body.points.len()];
+    let mut contacts: Vec<Contact> = vec![];
+
+    (0..settings.iterations).for_each(|_| {
+        body.springs
+            .iter()
+            .zip(lambdas.iter_mut())
+            .filter(|(spring, _)| spring.is_active())
+            .for_each(|(spring, lambda)| {
+                // Springs of stretched cells are made stiffer or softer as with the forces
+                let alpha = alpha / spring.stiffness_scale();
+                spring.solve_distance_constraint(&mut positions, &inverse_masses, lambda, alpha)
+            });
+
+        if let Some(bulk_modulus) = body.material().bulk_modulus {
+            body.triangles
+                .iter()
+                .zip(area_lambdas.iter_mut())
+                .for_each(|(triangle, lambda)| {
+                    triangle.solve_area_constraint(
+                        &mut positions,
+                        &inverse_masses,
+                        lambda,
+                        bulk_modulus,
+                        delta_t,
+                    )
+                });
+        }
+
+        if let Some(stiffness) = body.material().shape_matching {
+            solve_shape_constraints(
+                &body.points,
+                &mut positions,
+                &inverse_masses,
+                &mut shape_lambdas,
+                stiffness,
+                body.material().mass,
+                delta_t,
+            );
+        }
+
+        polygons.iter().for_each(|polygon| {
+            let surface = body.surface.combine(&polygon.surface);
+            body.points
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| inverse_masses[*index] > 0.)
+                .for_each(|(index, point)| {
+                    let mut probe = *point;
+                    probe.pos = positions[index];
+                    if let Some(push_vec) = probe.get_push_vector(polygon) {
+                        positions[index] += push_vec;
+                        let normal = push_vec.normalize();
+                        match contacts.iter_mut().find(|contact| contact.index == index) {
+                            Some(contact) => contact.depth += push_vec.length(),
+                            None => contacts.push(Contact {
+                                index,
+                                normal,
+                                depth: push_vec.length(),
+                                approaching_speed: velocities[index].dot(normal),
+                                surface,
+                            }),
+                        }
+                    }
+                });
+        });
+    });
+
+    let mut velocities = body
+        .points
+        .iter()
+        .zip(&positions)
+        .map(|(point, pos)| (*pos - point.pos) / delta_t)
+        .collect::<Vec<Vec2>>();
+
+    // Friction bounded by the depth of the contact and restitution of the approaching speed
+    contacts.iter().for_each(|contact| {
+        let vel = velocities[contact.index];
+        let normal_speed = vel.dot(contact.normal);
+        let tangent_vel = vel - contact.normal * normal_speed;
+        let tangent_speed = tangent_vel.length();
+
+        let tangent_vel = if tangent_speed * delta_t
+            <= contact.surface.static_friction * contact.depth
+        {
+            vec2(0., 0.)
+        } else {
+            tangent_vel
+                * (1.
+                    - contact.surface.kinetic_friction * contact.depth / (tangent_speed * delta_t))
+                    .max(0.)
+        };
+        // Resting contacts, only approaching by what a step of the load on them adds, stay put
+        let normal_speed = if contact.approaching_speed < 0. {
+            let bounce = (-contact.approaching_speed - RESTING_SPEED).max(0.);
+            normal_speed.max(contact.surface.restitution * bounce)
+        } else {
+            normal_speed
+        };
+
+        velocities[contact.index] = tangent_vel + contact.normal * normal_speed;
+    });
+
+    let max_speed = body.material().max_speed;
+    body.points
+        .iter_mut()
+        .zip(positions.into_iter().zip(velocities))
+        .for_each(|(point, (pos, vel))| point.update(pos, vel, max_speed));
+}
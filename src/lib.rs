@@ -1,15 +1,26 @@
 pub mod utils {
     pub mod conversion;
+    pub mod integrator;
     pub mod math;
+    pub mod mesh;
 }
 
 pub mod entities {
+    pub mod area;
     pub mod dot;
+    pub mod drag;
     pub mod incomplete_polygon;
     pub mod interaction;
+    pub mod lattice;
+    pub mod material;
     pub mod polygon;
+    pub mod pressure;
     pub mod segment;
+    pub mod shape_matching;
     pub mod soft_body;
+    pub mod xpbd;
 }
 
+pub mod timestep;
 pub mod ui;
+pub mod world;
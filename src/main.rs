@@ -1,27 +1,49 @@
 use macroquad::prelude::*;
+use soft_body_simulation::entities::dot::DELTA_T_RUNGE_KUTTA;
+use soft_body_simulation::entities::material::Material;
 use soft_body_simulation::entities::*;
+use soft_body_simulation::timestep::FixedTimestep;
 use soft_body_simulation::ui::*;
+use soft_body_simulation::world::World;
+
+fn generate_world() -> World {
+    let mut world = World::new(polygon::BoundingBox::new(&[
+        (-screen_width() * 2., -screen_height() * 2.),
+        (screen_width() * 2., screen_height() * 2.),
+    ]));
+    world.add_polygon(polygon::Polygon::generate_floor(
+        screen_width(),
+        screen_height(),
+    ));
+    world.add_body(soft_body::SoftBody::new(
+        screen_width() / 2.,
+        screen_height() / 2.,
+        None,
+        None,
+        None,
+        None,
+    ));
+    world
+}
 
 #[macroquad::main("Soft Body Simulation")]
 async fn main() {
     let mut creating_entity: Entities = Entities::Dot;
     let mut show_skeleton = false;
+    let mut material = Material::default();
+    let mut body_settings = BodySettings::default();
 
-    let buttons_window_dimensions = (265., 155.);
-
-    let mut polygons: Vec<polygon::Polygon> = vec![polygon::Polygon::generate_floor()];
+    let mut world = generate_world();
+    let mut timestep = FixedTimestep::new(DELTA_T_RUNGE_KUTTA, None, None, None);
     let mut drawing_polygon = incomplete_polygon::IncompletePolygon::new();
 
-    let mut soft_body = soft_body::SoftBody::new(screen_width() / 2., screen_height() / 2.);
-
-    let [polygon_button, stop_drawing_button, reset_button, soft_body_button, show_skeleton_button, show_border_button] =
+    let [polygon_button, stop_drawing_button, reset_button, soft_body_button, show_skeleton_button, show_border_button, delete_button, pin_button, drag_button, balloon_button, outline_button, cloth_button] =
         spawn_buttons();
 
     loop {
         clear_background(BLACK);
-        let is_mouse_on_buttons = mouse_position().0 < buttons_window_dimensions.0
-            && mouse_position().1 < buttons_window_dimensions.1;
         let is_creating_polygon = !drawing_polygon.points.is_empty();
+        let mut is_mouse_on_buttons = false;
 
         egui_macroquad::ui(|egui_ctx| {
             egui::Window::new("Controller")
@@ -42,9 +64,27 @@ async fn main() {
                         if ui.button(soft_body_button.clone()).clicked() {
                             creating_entity = Entities::Dot;
                         }
+                        if ui.button(balloon_button.clone()).clicked() {
+                            creating_entity = Entities::Balloon;
+                        }
+                        if ui.button(outline_button.clone()).clicked() {
+                            creating_entity = Entities::Outline;
+                        }
+                        if ui.button(cloth_button.clone()).clicked() {
+                            creating_entity = Entities::Cloth;
+                        }
                         if ui.button(polygon_button.clone()).clicked() {
                             creating_entity = Entities::Polygon;
                         }
+                        if ui.button(delete_button.clone()).clicked() {
+                            creating_entity = Entities::Delete;
+                        }
+                        if ui.button(pin_button.clone()).clicked() {
+                            creating_entity = Entities::Pin;
+                        }
+                        if ui.button(drag_button.clone()).clicked() {
+                            creating_entity = Entities::Drag;
+                        }
                     }
                     ui.separator();
                     if show_skeleton {
@@ -56,9 +96,16 @@ async fn main() {
                     }
                     if ui.button(reset_button.clone()).clicked() {
                         drawing_polygon.reset();
-                        polygons = vec![polygon::Polygon::generate_floor()];
+                        world.polygons = vec![polygon::Polygon::generate_floor(
+                            screen_width(),
+                            screen_height(),
+                        )];
                     }
                 });
+            show_simulation_window(egui_ctx, &mut world, &mut timestep);
+            show_material_window(egui_ctx, &mut world, &mut material);
+            show_body_window(egui_ctx, &mut body_settings);
+            is_mouse_on_buttons = egui_ctx.is_pointer_over_area();
         });
 
         // Draw things before egui
@@ -69,28 +116,38 @@ async fn main() {
         if is_mouse_button_pressed(MouseButton::Left) && !is_mouse_on_buttons {
             spawn_entity(
                 &creating_entity,
-                &mut polygons,
+                &mut world,
                 &mut drawing_polygon,
-                &mut soft_body,
+                &material,
+                &body_settings,
             );
         }
 
+        if is_mouse_button_released(MouseButton::Left) {
+            world.release();
+        }
+        world.drag.move_to(
+            vec2(mouse_position().0, mouse_position().1),
+            timestep.simulation_time(get_frame_time()),
+        );
+
         // Drawing polygons
-        polygons.iter().for_each(|poly| poly.draw());
+        world.polygons.iter().for_each(|poly| poly.draw());
 
         // Drawing In-progress polygon
-        drawing_polygon.draw(&polygons, &soft_body);
+        drawing_polygon.draw(&world.polygons, &world.bodies);
 
-        soft_body.update_runge_kutta();
+        timestep.advance(&mut world, get_frame_time());
 
-        polygons.iter().for_each(|poly| {
-            soft_body.handle_collision(poly);
+        world.bodies.iter().for_each(|body| {
+            if show_skeleton {
+                body.draw();
+            } else {
+                body.draw_border();
+            }
         });
-        if show_skeleton {
-            soft_body.draw();
-        } else {
-            soft_body.draw_border();
-        }
+
+        world.drag.draw(&world.bodies);
 
         draw_mouse_icon(&mut creating_entity);
 
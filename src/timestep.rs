@@ -0,0 +1,68 @@
+use crate::world::World;
+
+pub const DEFAULT_STEP_RATE: f32 = 60.;
+pub const DEFAULT_SUBSTEPS: usize = 1;
+pub const DEFAULT_MAX_STEPS: usize = 5;
+
+// Steps a World at a fixed rate of real time, so the simulation runs at the same speed
+// whatever the frame rate
+pub struct FixedTimestep {
+    // Simulation time covered by a step
+    pub delta_t: f32,
+    // Steps per second of real time
+    pub step_rate: f32,
+    // Each step is split in this many world updates
+    pub substeps: usize,
+    // Most steps taken in one frame, the rest of a long frame is dropped
+    pub max_steps: usize,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(
+        delta_t: f32,
+        step_rate: Option<f32>,
+        substeps: Option<usize>,
+        max_steps: Option<usize>,
+    ) -> FixedTimestep {
+        let max_steps = max_steps.unwrap_or(DEFAULT_MAX_STEPS);
+        // Without any step per frame the world would never move
+        assert!(
+            max_steps > 0,
+            "a timestep must take at least one step per frame"
+        );
+        FixedTimestep {
+            delta_t,
+            step_rate: step_rate.unwrap_or(DEFAULT_STEP_RATE),
+            substeps: substeps.unwrap_or(DEFAULT_SUBSTEPS),
+            max_steps,
+            accumulator: 0.,
+        }
+    }
+
+    // Simulation time matching a span of real time
+    pub fn simulation_time(&self, real_time: f32) -> f32 {
+        real_time * self.step_rate * self.delta_t
+    }
+
+    // Consumes the real time elapsed since the last frame, returning the number of steps taken
+    pub fn advance(&mut self, world: &mut World, frame_time: f32) -> usize {
+        let step_time = 1. / self.step_rate;
+        let substeps = self.substeps.max(1);
+        let max_steps = self.max_steps.max(1);
+        self.accumulator += frame_time;
+
+        let mut steps = 0;
+        while self.accumulator >= step_time && steps < max_steps {
+            (0..substeps).for_each(|_| world.step(self.delta_t / substeps as f32));
+            self.accumulator -= step_time;
+            steps += 1;
+        }
+
+        if steps == max_steps {
+            self.accumulator %= step_time;
+        }
+
+        steps
+    }
+}
@@ -1,10 +1,52 @@
+use super::entities::lattice::{lattices, Lattice, Resolution, MIN_CELL_SIZE};
+use super::entities::material::{
+    presets, Material, DEFAULT_BULK_MODULUS, DEFAULT_SHAPE_STIFFNESS, DEFAULT_TEAR_STRAIN,
+};
+use super::entities::soft_body::{DEFAULT_HEIGHT, DEFAULT_WIDTH};
 use super::entities::*;
+use super::timestep::FixedTimestep;
+use super::utils::integrator::integrators;
+use super::utils::mesh::DEFAULT_SPACING;
+use super::world::{Solver, World};
 use egui::text::{LayoutJob, TextFormat, TextStyle};
 use macroquad::prelude::*;
 
+const BALLOON_RADIUS: f32 = 60.;
+const DEFAULT_CELLS: (usize, usize) = (4, 3);
+
+// Shape of the soft bodies created with the mouse
+#[derive(Copy, Clone, Debug)]
+pub struct BodySettings {
+    pub size: (f32, f32),
+    pub resolution: Resolution,
+    pub lattice: Lattice,
+    // Whether the cloths hang from their top row
+    pub pin_cloth: bool,
+    // Material of the cloths, kept apart from the one of the other bodies
+    pub cloth_material: Material,
+}
+
+impl Default for BodySettings {
+    fn default() -> Self {
+        Self {
+            size: (DEFAULT_WIDTH, DEFAULT_HEIGHT),
+            resolution: Resolution::default(),
+            lattice: Lattice::default(),
+            pin_cloth: true,
+            cloth_material: Material::cloth(),
+        }
+    }
+}
+
 pub enum Entities {
     Dot,
+    Balloon,
+    Outline,
+    Cloth,
     Polygon,
+    Delete,
+    Pin,
+    Drag,
 }
 
 pub fn draw_mouse_icon(creating_entity: &mut Entities) {
@@ -18,6 +60,29 @@ pub fn draw_mouse_icon(creating_entity: &mut Entities) {
                 YELLOW,
             );
         }
+        Entities::Balloon => {
+            draw_circle_lines(
+                mouse_position().0 + 20.,
+                mouse_position().1 + 20.,
+                10.,
+                1.,
+                ORANGE,
+            );
+        }
+        Entities::Outline => {
+            let (x, y) = (mouse_position().0 + 15., mouse_position().1 + 15.);
+            draw_line(x, y + 20., x + 10., y, 2., YELLOW);
+            draw_line(x + 10., y, x + 20., y + 20., 2., YELLOW);
+            draw_line(x + 20., y + 20., x, y + 20., 2., YELLOW);
+        }
+        Entities::Cloth => {
+            let (x, y) = (mouse_position().0 + 15., mouse_position().1 + 15.);
+            (0..3).for_each(|line| {
+                let offset = line as f32 * 10.;
+                draw_line(x + offset, y, x + offset, y + 20., 1., YELLOW);
+                draw_line(x, y + offset, x + 20., y + offset, 1., YELLOW);
+            });
+        }
         Entities::Polygon => {
             draw_rectangle_lines(
                 mouse_position().0 + 15.,
@@ -28,30 +93,338 @@ pub fn draw_mouse_icon(creating_entity: &mut Entities) {
                 BLUE,
             );
         }
+        Entities::Delete => {
+            let (x, y) = (mouse_position().0 + 15., mouse_position().1 + 15.);
+            draw_line(x, y, x + 20., y + 20., 3., RED);
+            draw_line(x + 20., y, x, y + 20., 3., RED);
+        }
+        Entities::Pin => {
+            let (x, y) = (mouse_position().0 + 20., mouse_position().1 + 20.);
+            draw_line(x, y, x, y + 15., 2., WHITE);
+            draw_circle(x, y, 5., RED);
+        }
+        Entities::Drag => {
+            let (x, y) = (mouse_position().0 + 20., mouse_position().1 + 20.);
+            draw_circle_lines(x, y, 8., 2., SKYBLUE);
+            draw_circle(x, y, 3., SKYBLUE);
+        }
     }
 }
 
 pub fn spawn_entity(
     entity_type: &Entities,
-    polygons: &mut Vec<polygon::Polygon>,
+    world: &mut World,
     drawing_polygon: &mut incomplete_polygon::IncompletePolygon,
-    body: &mut soft_body::SoftBody,
+    material: &Material,
+    body: &BodySettings,
 ) {
     match entity_type {
         Entities::Dot => {
-            *body = soft_body::SoftBody::new(mouse_position().0, mouse_position().1);
+            world.add_body(soft_body::SoftBody::new(
+                mouse_position().0,
+                mouse_position().1,
+                Some(body.size),
+                Some(body.resolution),
+                Some(body.lattice),
+                Some(*material),
+            ));
+        }
+        Entities::Balloon => {
+            world.add_body(soft_body::SoftBody::new_balloon(
+                mouse_position().0,
+                mouse_position().1,
+                BALLOON_RADIUS,
+                None,
+                None,
+                Some(*material),
+            ));
+        }
+        Entities::Outline => {
+            if !drawing_polygon.is_intersecting_with_polygons(&world.polygons)
+                && !drawing_polygon.is_intersecting_with_soft_bodies(&world.bodies)
+            {
+                drawing_polygon.add_body_point(
+                    vec2(mouse_position().0, mouse_position().1),
+                    &mut world.bodies,
+                    material,
+                );
+            }
+        }
+        Entities::Cloth => {
+            world.add_body(soft_body::SoftBody::cloth(
+                mouse_position().0,
+                mouse_position().1,
+                Some(body.size),
+                Some(body.resolution),
+                body.pin_cloth,
+                Some(body.cloth_material),
+            ));
         }
         Entities::Polygon => {
-            if !drawing_polygon.is_intersecting_with_polygons(polygons)
-                && !drawing_polygon.is_intersecting_with_soft_body(body)
+            if !drawing_polygon.is_intersecting_with_polygons(&world.polygons)
+                && !drawing_polygon.is_intersecting_with_soft_bodies(&world.bodies)
+            {
+                drawing_polygon.add_point(
+                    vec2(mouse_position().0, mouse_position().1),
+                    &mut world.polygons,
+                );
+            }
+        }
+        Entities::Delete => {
+            let point = vec2(mouse_position().0, mouse_position().1);
+            // Cloths have no inside, so they are picked by their dots
+            if let Some(index) = world
+                .body_at(point)
+                .or_else(|| world.dot_at(point).map(|(body, _)| body))
             {
-                drawing_polygon.add_point(vec2(mouse_position().0, mouse_position().1), polygons);
+                world.remove_body(index);
             }
         }
+        Entities::Pin => {
+            if let Some((body, dot)) = world.dot_at(vec2(mouse_position().0, mouse_position().1)) {
+                let point = &mut world.bodies[body].points[dot];
+                if point.is_pinned() {
+                    point.unpin();
+                } else {
+                    point.pin(None);
+                }
+            }
+        }
+        Entities::Drag => {
+            world.grab(vec2(mouse_position().0, mouse_position().1));
+        }
+    }
+}
+
+pub fn show_simulation_window(
+    egui_ctx: &egui::CtxRef,
+    world: &mut World,
+    timestep: &mut FixedTimestep,
+) {
+    egui::Window::new("Simulation")
+        .anchor(egui::Align2::RIGHT_TOP, [0., 0.])
+        .resizable(false)
+        .show(egui_ctx, |ui| {
+            egui::ComboBox::from_label("Solver")
+                .selected_text(match world.solver {
+                    Solver::Forces => "Forces",
+                    Solver::Xpbd(_) => "XPBD",
+                })
+                .show_ui(ui, |ui| {
+                    if ui
+                        .selectable_label(matches!(world.solver, Solver::Forces), "Forces")
+                        .clicked()
+                    {
+                        world.solver = Solver::Forces;
+                    }
+                    if ui
+                        .selectable_label(matches!(world.solver, Solver::Xpbd(_)), "XPBD")
+                        .clicked()
+                        && matches!(world.solver, Solver::Forces)
+                    {
+                        world.solver = Solver::Xpbd(xpbd::XpbdSettings::default());
+                    }
+                });
+            if let Solver::Xpbd(settings) = &mut world.solver {
+                ui.add(egui::Slider::new(&mut settings.iterations, 1..=50).text("Iterations"));
+                ui.add(
+                    egui::Slider::new(&mut settings.compliance, 0.0..=0.1)
+                        .logarithmic(true)
+                        .text("Compliance"),
+                );
+            }
+            egui::ComboBox::from_label("Integrator")
+                .selected_text(world.integrator.name())
+                .show_ui(ui, |ui| {
+                    integrators().into_iter().for_each(|integrator| {
+                        if ui
+                            .selectable_label(
+                                integrator.name() == world.integrator.name(),
+                                integrator.name(),
+                            )
+                            .clicked()
+                        {
+                            world.integrator = integrator;
+                        }
+                    });
+                });
+            ui.add(egui::Slider::new(&mut timestep.substeps, 1..=10).text("Substeps"));
+            ui.add(egui::Slider::new(&mut timestep.max_steps, 1..=20).text("Max steps per frame"));
+            ui.add(egui::Slider::new(&mut world.gravity.y, -5.0..=10.0).text("Gravity"));
+            ui.add(
+                egui::Slider::new(&mut world.drag.stiffness, 0.1..=10.0)
+                    .logarithmic(true)
+                    .text("Drag stiffness"),
+            );
+            ui.add(egui::Slider::new(&mut world.drag.damping, 0.0..=5.0).text("Drag damping"));
+            ui.checkbox(&mut world.self_collision, "Soft-body self collision");
+            ui.label(format!("Energy: {:.0}", world.get_energy()));
+        });
+}
+
+// Shape of the generated soft bodies
+pub fn show_body_window(egui_ctx: &egui::CtxRef, body: &mut BodySettings) {
+    egui::Window::new("Soft-body")
+        .anchor(egui::Align2::LEFT_BOTTOM, [0., 0.])
+        .resizable(false)
+        .show(egui_ctx, |ui| {
+            egui::ComboBox::from_label("Lattice")
+                .selected_text(
+                    lattices()
+                        .into_iter()
+                        .find(|(_, option)| *option == body.lattice)
+                        .map_or("", |(name, _)| name),
+                )
+                .show_ui(ui, |ui| {
+                    lattices().into_iter().for_each(|(name, option)| {
+                        ui.selectable_value(&mut body.lattice, option, name);
+                    });
+                });
+            ui.add(egui::Slider::new(&mut body.size.0, 20.0..=600.0).text("Width"));
+            ui.add(egui::Slider::new(&mut body.size.1, 20.0..=400.0).text("Height"));
+
+            let mut explicit_cells = matches!(body.resolution, Resolution::Cells(_, _));
+            if ui
+                .checkbox(&mut explicit_cells, "Number of cells")
+                .changed()
+            {
+                body.resolution = if explicit_cells {
+                    Resolution::Cells(DEFAULT_CELLS.0, DEFAULT_CELLS.1)
+                } else {
+                    Resolution::CellSize(DEFAULT_SPACING)
+                };
+            }
+            match &mut body.resolution {
+                Resolution::CellSize(cell_size) => {
+                    ui.add(egui::Slider::new(cell_size, MIN_CELL_SIZE..=100.0).text("Cell size"));
+                }
+                Resolution::Cells(columns, rows) => {
+                    // No more cells than fit at the smallest size
+                    let most = |length: f32| ((length / MIN_CELL_SIZE) as usize).max(1);
+                    ui.add(egui::Slider::new(columns, 1..=most(body.size.0)).text("Columns"));
+                    ui.add(egui::Slider::new(rows, 1..=most(body.size.1)).text("Rows"));
+                }
+            }
+            ui.checkbox(&mut body.pin_cloth, "Pin cloth top row");
+        });
+}
+
+// Material of the new soft-bodies, also applied to the existing ones but the cloths when changed
+pub fn show_material_window(egui_ctx: &egui::CtxRef, world: &mut World, material: &mut Material) {
+    let previous = *material;
+    egui::Window::new("Material")
+        .anchor(egui::Align2::RIGHT_BOTTOM, [0., 0.])
+        .resizable(false)
+        .show(egui_ctx, |ui| {
+            egui::ComboBox::from_label("Preset")
+                .selected_text(
+                    presets()
+                        .into_iter()
+                        .find(|(_, preset)| preset == material)
+                        .map_or("Custom", |(name, _)| name),
+                )
+                .show_ui(ui, |ui| {
+                    presets().into_iter().for_each(|(name, preset)| {
+                        if ui.selectable_label(preset == *material, name).clicked() {
+                            *material = preset;
+                        }
+                    });
+                });
+            ui.add(egui::Slider::new(&mut material.stiffness, 0.5..=30.0).text("Stiffness"));
+            ui.add(
+                egui::Slider::new(&mut material.border_stiffness, 0.5..=30.0)
+                    .text("Border stiffness"),
+            );
+            ui.add(egui::Slider::new(&mut material.damping, 0.0..=3.0).text("Damping"));
+            ui.add(egui::Slider::new(&mut material.mass, 0.2..=5.0).text("Density"));
+            ui.add(egui::Slider::new(&mut material.radius, 2.0..=20.0).text("Dot radius"));
+            ui.add(egui::Slider::new(&mut material.max_speed, 10.0..=200.0).text("Max speed"));
+            optional_slider(
+                ui,
+                &mut material.shear_stiffness,
+                ("Shear springs", "Shear stiffness"),
+                material.stiffness,
+                0.5..=30.0,
+            );
+            optional_slider(
+                ui,
+                &mut material.bend_stiffness,
+                ("Bend springs", "Bend stiffness"),
+                material.stiffness,
+                0.5..=30.0,
+            );
+            optional_slider(
+                ui,
+                &mut material.shape_matching,
+                ("Shape matching", "Shape stiffness"),
+                DEFAULT_SHAPE_STIFFNESS,
+                0.05..=5.0,
+            );
+            optional_slider(
+                ui,
+                &mut material.bulk_modulus,
+                ("Area preservation", "Bulk modulus"),
+                DEFAULT_BULK_MODULUS,
+                0.5..=10.0,
+            );
+            optional_slider(
+                ui,
+                &mut material.tear_strain,
+                ("Tearing", "Tear strain"),
+                DEFAULT_TEAR_STRAIN,
+                0.1..=3.0,
+            );
+            let mut plastic = material.plasticity.is_some();
+            ui.checkbox(&mut plastic, "Plasticity");
+            material.plasticity = match (plastic, material.plasticity) {
+                (false, _) => None,
+                (true, plasticity) => Some(plasticity.unwrap_or_default()),
+            };
+            if let Some(plasticity) = &mut material.plasticity {
+                ui.add(
+                    egui::Slider::new(&mut plasticity.yield_ratio, 0.0..=0.5).text("Yield ratio"),
+                );
+                ui.add(egui::Slider::new(&mut plasticity.creep, 0.0..=2.0).text("Creep"));
+                ui.add(
+                    egui::Slider::new(&mut plasticity.max_deformation, 0.0..=0.9)
+                        .text("Max deformation"),
+                );
+            }
+        });
+
+    if *material != previous {
+        world
+            .bodies
+            .iter_mut()
+            .filter(|body| !body.is_cloth())
+            .for_each(|body| body.set_material(*material));
+    }
+}
+
+// Checkbox turning a parameter on, with a logarithmic slider to tune it when on
+fn optional_slider(
+    ui: &mut egui::Ui,
+    value: &mut Option<f32>,
+    (checkbox_label, slider_label): (&str, &str),
+    default: f32,
+    range: std::ops::RangeInclusive<f32>,
+) {
+    let mut is_on = value.is_some();
+    ui.checkbox(&mut is_on, checkbox_label);
+    *value = match (is_on, *value) {
+        (false, _) => None,
+        (true, current) => Some(current.unwrap_or(default)),
+    };
+    if let Some(value) = value {
+        ui.add(
+            egui::Slider::new(value, range)
+                .logarithmic(true)
+                .text(slider_label),
+        );
     }
 }
 
-pub fn spawn_buttons() -> [LayoutJob; 6] {
+pub fn spawn_buttons() -> [LayoutJob; 12] {
     let mut polygon_button = LayoutJob::default();
     polygon_button.append(
         "⬜ ",
@@ -172,6 +545,126 @@ pub fn spawn_buttons() -> [LayoutJob; 6] {
         },
     );
 
+    let mut delete_button = LayoutJob::default();
+    delete_button.append(
+        "🗑 ",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::RED,
+            ..Default::default()
+        },
+    );
+    delete_button.append(
+        "Delete Soft-body",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::WHITE,
+            ..Default::default()
+        },
+    );
+
+    let mut pin_button = LayoutJob::default();
+    pin_button.append(
+        "📌 ",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::RED,
+            ..Default::default()
+        },
+    );
+    pin_button.append(
+        "Pin Dot",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::WHITE,
+            ..Default::default()
+        },
+    );
+
+    let mut drag_button = LayoutJob::default();
+    drag_button.append(
+        "✋ ",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::LIGHT_BLUE,
+            ..Default::default()
+        },
+    );
+    drag_button.append(
+        "Drag Soft-body",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::WHITE,
+            ..Default::default()
+        },
+    );
+
+    let mut balloon_button = LayoutJob::default();
+    balloon_button.append(
+        "🎈 ",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::from_rgb(255, 165, 0),
+            ..Default::default()
+        },
+    );
+    balloon_button.append(
+        "Create Balloon",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::WHITE,
+            ..Default::default()
+        },
+    );
+
+    let mut outline_button = LayoutJob::default();
+    outline_button.append(
+        "✏ ",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::YELLOW,
+            ..Default::default()
+        },
+    );
+    outline_button.append(
+        "Draw Soft-body",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::WHITE,
+            ..Default::default()
+        },
+    );
+
+    let mut cloth_button = LayoutJob::default();
+    cloth_button.append(
+        "🏁 ",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::YELLOW,
+            ..Default::default()
+        },
+    );
+    cloth_button.append(
+        "Create Cloth",
+        0.0,
+        TextFormat {
+            style: TextStyle::Heading,
+            color: egui::Color32::WHITE,
+            ..Default::default()
+        },
+    );
+
     [
         polygon_button,
         stop_drawing_button,
@@ -179,5 +672,11 @@ pub fn spawn_buttons() -> [LayoutJob; 6] {
         soft_body_button,
         show_skeleton_button,
         show_border_button,
+        delete_button,
+        pin_button,
+        drag_button,
+        balloon_button,
+        outline_button,
+        cloth_button,
     ]
 }
@@ -0,0 +1,110 @@
+use crate::utils::math::*;
+use macroquad::prelude::Vec2;
+
+// Method used to move a system of points through a time step
+pub trait Integrator {
+    fn name(&self) -> &'static str;
+
+    // Largest product of the step and the fastest angular frequency of the points at which the
+    // integration stays stable
+    fn stable_step(&self) -> f32;
+
+    fn integrate(
+        &self,
+        acceleration_func: &AccelerationFunc,
+        positions: &[Vec2],
+        velocities: &[Vec2],
+        delta_t: f32,
+    ) -> (Vec<Vec2>, Vec<Vec2>);
+}
+
+// Updates the velocities first and moves the points with the new ones
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn name(&self) -> &'static str {
+        "Semi-implicit Euler"
+    }
+
+    fn stable_step(&self) -> f32 {
+        1.5
+    }
+
+    fn integrate(
+        &self,
+        acceleration_func: &AccelerationFunc,
+        positions: &[Vec2],
+        velocities: &[Vec2],
+        delta_t: f32,
+    ) -> (Vec<Vec2>, Vec<Vec2>) {
+        let velocities = offset(
+            velocities,
+            &acceleration_func(positions, velocities),
+            delta_t,
+        );
+        (offset(positions, &velocities, delta_t), velocities)
+    }
+}
+
+// Moves the points by half a step, updates the velocities there and moves the
+// other half (drift-kick-drift), which is second order and keeps the energy bounded
+pub struct PositionVerlet;
+
+impl Integrator for PositionVerlet {
+    fn name(&self) -> &'static str {
+        "Position Verlet"
+    }
+
+    fn stable_step(&self) -> f32 {
+        1.5
+    }
+
+    fn integrate(
+        &self,
+        acceleration_func: &AccelerationFunc,
+        positions: &[Vec2],
+        velocities: &[Vec2],
+        delta_t: f32,
+    ) -> (Vec<Vec2>, Vec<Vec2>) {
+        let half_positions = offset(positions, velocities, delta_t / 2.);
+        let velocities = offset(
+            velocities,
+            &acceleration_func(&half_positions, velocities),
+            delta_t,
+        );
+        (
+            offset(&half_positions, &velocities, delta_t / 2.),
+            velocities,
+        )
+    }
+}
+
+pub struct RungeKutta;
+
+impl Integrator for RungeKutta {
+    fn name(&self) -> &'static str {
+        "Runge-Kutta 4"
+    }
+
+    fn stable_step(&self) -> f32 {
+        2.
+    }
+
+    fn integrate(
+        &self,
+        acceleration_func: &AccelerationFunc,
+        positions: &[Vec2],
+        velocities: &[Vec2],
+        delta_t: f32,
+    ) -> (Vec<Vec2>, Vec<Vec2>) {
+        runge_kutta_integration(acceleration_func, positions, velocities, delta_t)
+    }
+}
+
+pub fn integrators() -> Vec<Box<dyn Integrator>> {
+    vec![
+        Box::new(SemiImplicitEuler),
+        Box::new(PositionVerlet),
+        Box::new(RungeKutta),
+    ]
+}
@@ -54,51 +54,69 @@ pub fn close_to_equal(num1: f32, num2: f32) -> bool {
     (num1 - num2).abs() < 0.001
 }
 
-pub const DELTA_T_RUNGE_KUTTA: f32 = 0.25;
+// Accelerations of a system of points given their positions and velocities
+pub type AccelerationFunc<'a> = dyn Fn(&[Vec2], &[Vec2]) -> Vec<Vec2> + 'a;
+
+// Moves every value along its slope
+pub fn offset(values: &[Vec2], slopes: &[Vec2], factor: f32) -> Vec<Vec2> {
+    values
+        .iter()
+        .zip(slopes)
+        .map(|(value, slope)| *value + *slope * factor)
+        .collect()
+}
+
+// Integrates the motion of a whole system of points at once, where
+// the acceleration of each point can depend on the state of all the others
 pub fn runge_kutta_integration(
-    motion_func: &(dyn Fn(Vec2, Vec2, Vec2, Vec2) -> (Vec2, Vec2)),
-    pos_1: Vec2,
-    pos_2: Vec2,
-    vel_1: Vec2,
-    vel_2: Vec2,
-) -> (Vec2, Vec2) {
+    acceleration_func: &AccelerationFunc,
+    positions: &[Vec2],
+    velocities: &[Vec2],
+    delta_t: f32,
+) -> (Vec<Vec2>, Vec<Vec2>) {
     // Slope 1
-    let (f1_1, f1_2) = motion_func(pos_1, pos_2, vel_1, vel_2);
-    let (k1_1, k1_2) = (f1_1 * DELTA_T_RUNGE_KUTTA, f1_2 * DELTA_T_RUNGE_KUTTA);
+    let k1_pos = velocities.to_vec();
+    let k1_vel = acceleration_func(positions, velocities);
 
     // Slope 2
-    let k1_1_halved = k1_1 / 2.;
-    let k1_2_halved = k1_2 / 2.;
-    let (f2_1, f2_2) = motion_func(
-        pos_1 + (k1_1_halved * DELTA_T_RUNGE_KUTTA),
-        pos_2 + (k1_2_halved * DELTA_T_RUNGE_KUTTA),
-        vel_1 + k1_1_halved,
-        vel_2 + k1_2_halved,
+    let k2_pos = offset(velocities, &k1_vel, delta_t / 2.);
+    let k2_vel = acceleration_func(
+        &offset(positions, &k1_pos, delta_t / 2.),
+        &offset(velocities, &k1_vel, delta_t / 2.),
     );
 
-    let (k2_1, k2_2) = (f2_1 * DELTA_T_RUNGE_KUTTA, f2_2 * DELTA_T_RUNGE_KUTTA);
-
     // Slope 3
-    let k2_1_halved = k2_1 / 2.;
-    let k2_2_halved = k2_2 / 2.;
-    let (f3_1, f3_2) = motion_func(
-        pos_1 + (k2_1_halved * DELTA_T_RUNGE_KUTTA),
-        pos_2 + (k2_2_halved * DELTA_T_RUNGE_KUTTA),
-        vel_1 + k2_1_halved,
-        vel_2 + k2_2_halved,
+    let k3_pos = offset(velocities, &k2_vel, delta_t / 2.);
+    let k3_vel = acceleration_func(
+        &offset(positions, &k2_pos, delta_t / 2.),
+        &offset(velocities, &k2_vel, delta_t / 2.),
     );
-    let (k3_1, k3_2) = (f3_1 * DELTA_T_RUNGE_KUTTA, f3_2 * DELTA_T_RUNGE_KUTTA);
 
     // Slope 4
-    let (f4_1, f4_2) = motion_func(
-        pos_1 + (k3_1 * DELTA_T_RUNGE_KUTTA),
-        pos_2 + (k3_2 * DELTA_T_RUNGE_KUTTA),
-        vel_1 + k3_1,
-        vel_2 + k3_2,
+    let k4_pos = offset(velocities, &k3_vel, delta_t);
+    let k4_vel = acceleration_func(
+        &offset(positions, &k3_pos, delta_t),
+        &offset(velocities, &k3_vel, delta_t),
     );
-    let (k4_1, k4_2) = (f4_1 * DELTA_T_RUNGE_KUTTA, f4_2 * DELTA_T_RUNGE_KUTTA);
-    let push_vec_1 = (1. / 6.) * (k1_1 + (2. * k2_1) + (2. * k3_1) + k4_1);
-    let push_vec_2 = (1. / 6.) * (k1_2 + (2. * k2_2) + (2. * k3_2) + k4_2);
 
-    (push_vec_1, push_vec_2)
+    let weighted_sum = |values: &[Vec2], k1: &[Vec2], k2: &[Vec2], k3: &[Vec2], k4: &[Vec2]| {
+        (0..values.len())
+            .map(|i| values[i] + (delta_t / 6.) * (k1[i] + (2. * k2[i]) + (2. * k3[i]) + k4[i]))
+            .collect::<Vec<Vec2>>()
+    };
+
+    (
+        weighted_sum(positions, &k1_pos, &k2_pos, &k3_pos, &k4_pos),
+        weighted_sum(velocities, &k1_vel, &k2_vel, &k3_vel, &k4_vel),
+    )
+}
+
+// Area of the polygon closed by the points, positive when they go clockwise on screen
+pub fn signed_area(points: &[Vec2]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(point, next)| point.x * next.y - next.x * point.y)
+        .sum::<f32>()
+        / 2.
 }
@@ -0,0 +1,800 @@
+use macroquad::prelude::{vec2, Vec2};
+use std::f32::consts::PI;
+
+pub const DEFAULT_SPACING: f32 = 40.;
+pub const DEFAULT_MIN_ANGLE: f32 = 25.;
+
+// Triangles flatter than this are left out of the mesh
+const MIN_AREA: f32 = 0.001;
+// Points the refinement can add, as small angles in the boundary can keep it going forever
+const MAX_REFINEMENT_POINTS: usize = 2000;
+// Points closer than this to an existing one are not inserted
+const MIN_POINT_DISTANCE: f32 = 0.01;
+
+// Limits on the shape and size of the triangles of a mesh
+#[derive(Copy, Clone, Debug)]
+pub struct MeshQuality {
+    // Smallest angle of a triangle, in degrees. Above about 30 the refinement may not end
+    pub min_angle: f32,
+    pub max_edge_length: Option<f32>,
+}
+
+impl Default for MeshQuality {
+    fn default() -> Self {
+        Self {
+            min_angle: DEFAULT_MIN_ANGLE,
+            max_edge_length: None,
+        }
+    }
+}
+
+pub fn is_inside_outline(outline: &[Vec2], point: Vec2) -> bool {
+    outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .filter(|(start, end)| {
+            (start.y > point.y) != (end.y > point.y)
+                && point.x < start.x + (point.y - start.y) * (end.x - start.x) / (end.y - start.y)
+        })
+        .count()
+        % 2
+        != 0
+}
+
+fn distance_to_segment(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let side = end - start;
+    let along = if side.length_squared() > 0. {
+        ((point - start).dot(side) / side.length_squared()).clamp(0., 1.)
+    } else {
+        0.
+    };
+    (point - (start + side * along)).length()
+}
+
+fn bounds(points: &[Vec2]) -> (Vec2, Vec2) {
+    points.iter().fold(
+        (
+            vec2(f32::INFINITY, f32::INFINITY),
+            vec2(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(min, max), point| (min.min(*point), max.max(*point)),
+    )
+}
+
+// Grid of points inside the outline, kept away from it by half the spacing
+pub fn fill_outline(outline: &[Vec2], spacing: f32) -> Vec<Vec2> {
+    let (min, max) = bounds(outline);
+    let (columns, rows) = (
+        ((max.x - min.x) / spacing) as usize,
+        ((max.y - min.y) / spacing) as usize,
+    );
+    // Centering the grid in the bounding box of the outline
+    let corner = (min + max - vec2(columns as f32, rows as f32) * spacing) / 2.;
+    (0..=columns)
+        .flat_map(|column| (0..=rows).map(move |row| (column, row)))
+        .map(|(column, row)| corner + vec2(column as f32, row as f32) * spacing)
+        .filter(|point| {
+            is_inside_outline(outline, *point)
+                && outline
+                    .iter()
+                    .zip(outline.iter().cycle().skip(1))
+                    .all(|(start, end)| distance_to_segment(*point, *start, *end) > spacing / 2.)
+        })
+        .collect()
+}
+
+fn triangle_area(points: &[Vec2], [a, b, c]: [usize; 3]) -> f32 {
+    (points[b] - points[a]).perp_dot(points[c] - points[a]) / 2.
+}
+
+fn centroid(points: &[Vec2], triangle: [usize; 3]) -> Vec2 {
+    triangle
+        .iter()
+        .fold(vec2(0., 0.), |acc, &index| acc + points[index])
+        / 3.
+}
+
+fn circumcenter(points: &[Vec2], [a, b, c]: [usize; 3]) -> Vec2 {
+    let (b, c, a) = (points[b] - points[a], points[c] - points[a], points[a]);
+    let denominator = 2. * b.perp_dot(c);
+    a + (b.perp() * c.length_squared() - c.perp() * b.length_squared()) / denominator
+}
+
+fn min_angle(points: &[Vec2], [a, b, c]: [usize; 3]) -> f32 {
+    [(a, b, c), (b, c, a), (c, a, b)]
+        .iter()
+        .map(|&(corner, next, previous)| {
+            (points[next] - points[corner])
+                .normalize_or_zero()
+                .dot((points[previous] - points[corner]).normalize_or_zero())
+                .clamp(-1., 1.)
+                .acos()
+        })
+        .fold(f32::INFINITY, f32::min)
+        .to_degrees()
+}
+
+fn max_edge_length(points: &[Vec2], [a, b, c]: [usize; 3]) -> f32 {
+    [(a, b), (b, c), (c, a)]
+        .iter()
+        .map(|&(start, end)| (points[end] - points[start]).length())
+        .fold(0., f32::max)
+}
+
+// Whether the point lies inside the circle through the corners of the triangle,
+// computed in double precision as the points of a grid are often on the same circle
+fn is_in_circumcircle(points: &[Vec2], [a, b, c]: [usize; 3], point: Vec2) -> bool {
+    let [a, b, c] = [a, b, c].map(|index| {
+        (
+            (points[index].x - point.x) as f64,
+            (points[index].y - point.y) as f64,
+        )
+    });
+    let lifted = |(x, y): (f64, f64)| x * x + y * y;
+    let determinant = a.0 * (b.1 * lifted(c) - lifted(b) * c.1)
+        - a.1 * (b.0 * lifted(c) - lifted(b) * c.0)
+        + lifted(a) * (b.0 * c.1 - b.1 * c.0);
+    let orientation = (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0);
+    determinant * orientation.signum() > 0.
+}
+
+// Whether the point lies inside the circle having the segment as diameter
+fn is_encroaching(point: Vec2, start: Vec2, end: Vec2) -> bool {
+    (start - point).dot(end - point) < -MIN_POINT_DISTANCE * (end - start).length()
+}
+
+// Delaunay triangulation built by adding the points one at a time, starting from a triangle
+// containing them all (Bowyer-Watson). The corners of that first triangle are the first
+// three vertices
+struct Delaunay {
+    vertices: Vec<Vec2>,
+    triangles: Vec<[usize; 3]>,
+}
+
+impl Delaunay {
+    fn new(min: Vec2, max: Vec2) -> Delaunay {
+        let (center, size) = ((min + max) / 2., (max - min).max_element().max(1.) * 20.);
+        Delaunay {
+            vertices: vec![
+                center + vec2(-size, -size),
+                center + vec2(size, -size),
+                center + vec2(0., size),
+            ],
+            triangles: vec![[0, 1, 2]],
+        }
+    }
+
+    fn insert(&mut self, point: Vec2) -> usize {
+        if let Some(index) = self
+            .vertices
+            .iter()
+            .position(|vertex| (*vertex - point).length() < MIN_POINT_DISTANCE)
+        {
+            return index;
+        }
+        let index = self.vertices.len();
+        self.vertices.push(point);
+
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) = self
+            .triangles
+            .iter()
+            .partition(|&&triangle| is_in_circumcircle(&self.vertices, triangle, point));
+
+        // The sides of the hole left by the bad triangles are the ones they do not share
+        let sides = bad
+            .iter()
+            .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+            .collect::<Vec<(usize, usize)>>();
+        let hole = sides
+            .iter()
+            .filter(|&&(start, end)| {
+                sides
+                    .iter()
+                    .filter(|&&side| side == (start, end) || side == (end, start))
+                    .count()
+                    == 1
+            })
+            .map(|&(start, end)| [start, end, index]);
+        self.triangles = good.into_iter().chain(hole).collect();
+        index
+    }
+
+    fn has_edge(&self, start: usize, end: usize) -> bool {
+        self.triangles
+            .iter()
+            .any(|triangle| triangle.contains(&start) && triangle.contains(&end))
+    }
+
+    // Triangles between the inserted points only
+    fn inner_triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.triangles
+            .iter()
+            .filter(|triangle| triangle.iter().all(|&index| index > 2))
+            .copied()
+    }
+}
+
+// Delaunay triangulation of the points
+pub fn triangulate(points: &[Vec2]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return vec![];
+    }
+    let (min, max) = bounds(points);
+    let mut delaunay = Delaunay::new(min, max);
+    points.iter().for_each(|point| {
+        delaunay.insert(*point);
+    });
+
+    delaunay
+        .inner_triangles()
+        .map(|triangle| triangle.map(|index| index - 3))
+        .filter(|&triangle| triangle_area(points, triangle).abs() > MIN_AREA)
+        .collect()
+}
+
+// Splits a segment of the boundary at its middle, returning whether it was split, which
+// a segment too short or whose middle is already a vertex is not
+fn split_segment(
+    delaunay: &mut Delaunay,
+    segments: &mut Vec<(usize, usize)>,
+    index: usize,
+) -> bool {
+    let (start, end) = segments[index];
+    let (start_pos, end_pos) = (delaunay.vertices[start], delaunay.vertices[end]);
+    if (end_pos - start_pos).length() <= 2. * MIN_POINT_DISTANCE {
+        return false;
+    }
+    let count = delaunay.vertices.len();
+    let middle = delaunay.insert((start_pos + end_pos) / 2.);
+    if middle < count {
+        return false;
+    }
+    segments.splice(index..=index, [(start, middle), (middle, end)]);
+    true
+}
+
+// Triangulation of the inside of the boundary through the points, keeping the sides of the
+// boundary as edges and refining it until the triangles meet the quality (Ruppert). Sides
+// are split at their middle when a point falls in the circle they are the diameter of,
+// and bad triangles get a point added at their circumcenter
+pub fn constrained_triangulation(
+    boundary: &[Vec2],
+    points: &[Vec2],
+    quality: &MeshQuality,
+) -> (Vec<Vec2>, Vec<[usize; 3]>) {
+    if boundary.len() < 3 {
+        return (vec![], vec![]);
+    }
+    let (min, max) = bounds(boundary);
+    let mut delaunay = Delaunay::new(min, max);
+    let corners = boundary
+        .iter()
+        .map(|point| delaunay.insert(*point))
+        .collect::<Vec<usize>>();
+    let mut segments = corners
+        .iter()
+        .zip(corners.iter().cycle().skip(1))
+        .map(|(&start, &end)| (start, end))
+        .filter(|(start, end)| start != end)
+        .collect::<Vec<(usize, usize)>>();
+    points
+        .iter()
+        .filter(|point| is_inside_outline(boundary, **point))
+        .for_each(|point| {
+            delaunay.insert(*point);
+        });
+    let is_inside = |delaunay: &Delaunay, triangle: [usize; 3]| {
+        is_inside_outline(boundary, centroid(&delaunay.vertices, triangle))
+    };
+
+    let max_vertices = delaunay.vertices.len() + MAX_REFINEMENT_POINTS;
+    while delaunay.vertices.len() < max_vertices {
+        let vertices = &delaunay.vertices;
+        let should_split = |&(start, end): &(usize, usize)| {
+            let (start_pos, end_pos) = (vertices[start], vertices[end]);
+            (end_pos - start_pos).length() > 2. * MIN_POINT_DISTANCE
+                && (quality
+                    .max_edge_length
+                    .is_some_and(|length| (end_pos - start_pos).length() > length)
+                    || !delaunay.has_edge(start, end)
+                    || vertices.iter().enumerate().any(|(index, vertex)| {
+                        index > 2 && is_encroaching(*vertex, start_pos, end_pos)
+                    }))
+        };
+        if let Some(index) = segments.iter().position(should_split) {
+            if !split_segment(&mut delaunay, &mut segments, index) {
+                break;
+            }
+            continue;
+        }
+
+        let bad_triangle = delaunay.inner_triangles().find(|&triangle| {
+            is_inside(&delaunay, triangle)
+                && triangle_area(&delaunay.vertices, triangle).abs() > MIN_AREA
+                && (min_angle(&delaunay.vertices, triangle) < quality.min_angle
+                    || quality.max_edge_length.is_some_and(|length| {
+                        max_edge_length(&delaunay.vertices, triangle) > length
+                    }))
+        });
+        let center = match bad_triangle {
+            Some(triangle) => circumcenter(&delaunay.vertices, triangle),
+            None => break,
+        };
+
+        // A circumcenter close to the boundary splits the sides it would crowd instead
+        let encroached = segments
+            .iter()
+            .filter(|(start, end)| {
+                is_encroaching(center, delaunay.vertices[*start], delaunay.vertices[*end])
+            })
+            .copied()
+            .collect::<Vec<(usize, usize)>>();
+        if !encroached.is_empty() {
+            let mut is_split = false;
+            encroached.iter().for_each(|segment| {
+                if let Some(index) = segments.iter().position(|other| other == segment) {
+                    is_split |= split_segment(&mut delaunay, &mut segments, index);
+                }
+            });
+            // Nothing left to split, the refinement would go on forever
+            if !is_split {
+                break;
+            }
+        } else if is_inside_outline(boundary, center) {
+            let count = delaunay.vertices.len();
+            if delaunay.insert(center) < count {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+
+    // Keeping the triangles inside the boundary and the points they use
+    let mut new_index = vec![None; delaunay.vertices.len()];
+    let mut used_points = vec![];
+    let triangles = delaunay
+        .inner_triangles()
+        .filter(|&triangle| {
+            is_inside(&delaunay, triangle)
+                && triangle_area(&delaunay.vertices, triangle).abs() > MIN_AREA
+        })
+        .collect::<Vec<[usize; 3]>>()
+        .iter()
+        .map(|triangle| {
+            triangle.map(|index| {
+                *new_index[index].get_or_insert_with(|| {
+                    used_points.push(delaunay.vertices[index]);
+                    used_points.len() - 1
+                })
+            })
+        })
+        .collect();
+    (used_points, triangles)
+}
+
+// Points filling the outline about the spacing apart and the triangles covering it
+pub fn mesh_outline(outline: &[Vec2], spacing: f32) -> (Vec<Vec2>, Vec<[usize; 3]>) {
+    constrained_triangulation(
+        outline,
+        &fill_outline(outline, spacing),
+        &MeshQuality {
+            max_edge_length: Some(spacing * 1.5),
+            ..MeshQuality::default()
+        },
+    )
+}
+
+// Index of the point among the points, adding it if it is not there yet
+pub fn add_point(points: &mut Vec<Vec2>, point: Vec2) -> usize {
+    points
+        .iter()
+        .position(|other| (*other - point).length() < MIN_POINT_DISTANCE)
+        .unwrap_or_else(|| {
+            points.push(point);
+            points.len() - 1
+        })
+}
+
+fn rotate(vector: Vec2, angle: f32) -> Vec2 {
+    let (sin, cos) = angle.sin_cos();
+    vec2(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos,
+    )
+}
+
+// Closes the gap between two rings of points, each given with its position along the rings
+// from 0 to the period, by always moving on the ring whose next point comes first
+pub fn stitch_rings(
+    inner: &[(f32, usize)],
+    outer: &[(f32, usize)],
+    period: f32,
+) -> Vec<[usize; 3]> {
+    let (mut i, mut j) = (0, 0);
+    let mut triangles = vec![];
+    // Position of the point after, the first one coming again after a period
+    let next = |ring: &[(f32, usize)], index: usize| {
+        ring.get(index + 1)
+            .map_or(period + ring[0].0, |point| point.0)
+    };
+    while i < inner.len() || j < outer.len() {
+        let triangle = if j == outer.len() || (i < inner.len() && next(inner, i) <= next(outer, j))
+        {
+            i += 1;
+            [
+                inner[i - 1].1,
+                inner[i % inner.len()].1,
+                outer[j % outer.len()].1,
+            ]
+        } else {
+            j += 1;
+            [
+                inner[i % inner.len()].1,
+                outer[j % outer.len()].1,
+                outer[j - 1].1,
+            ]
+        };
+        // The rings shrinking to a point or a segment give flat triangles
+        if triangle[0] != triangle[1] && triangle[1] != triangle[2] && triangle[2] != triangle[0] {
+            triangles.push(triangle);
+        }
+    }
+    triangles
+}
+
+// Mesh of the shape around the segment between the two points up to the radius, leaving
+// a hole of the inner radius. It is made of rings going around the segment, each one
+// being its sides followed by its half circles, the outer one with the given number of
+// dots, as long as each side gets one and each half circle two. A segment of no length
+// gives a disk, with at least three dots
+pub fn radial_mesh(
+    start: Vec2,
+    end: Vec2,
+    inner_radius: f32,
+    radius: f32,
+    segments: usize,
+    rings: usize,
+) -> (Vec<Vec2>, Vec<[usize; 3]>) {
+    let (length, side) = ((end - start).length(), (end - start).normalize_or_zero());
+    let side = if side == Vec2::ZERO {
+        vec2(1., 0.)
+    } else {
+        side
+    };
+    let spacing = (2. * length + 2. * PI * radius) / segments.max(3) as f32;
+    let rings = rings.max(1);
+
+    let mut points = vec![];
+    let ring_points = (0..=rings)
+        .map(|ring| {
+            let ring_radius = inner_radius + (radius - inner_radius) * ring as f32 / rings as f32;
+            // Leaving at least two dots to each half circle of the outer ring
+            let side_count = if length > 0. {
+                ((length / spacing).round() as usize)
+                    .min(segments.saturating_sub(4) / 2)
+                    .max(1)
+            } else {
+                0
+            };
+            // The dots left by the sides go to the half circles, the first one taking
+            // the odd dot
+            let ring_count = ((2. * length + 2. * PI * ring_radius) / spacing).round() as usize;
+            let arcs_count = ring_count
+                .saturating_sub(2 * side_count)
+                .max(if length > 0. { 4 } else { 3 });
+            let arc_counts = [arcs_count - arcs_count / 2, arcs_count / 2];
+            let fractions = |count: usize| (0..count).map(move |index| index as f32 / count as f32);
+            // A ring of no size is the segment, its half circles shrinking to the ends
+            let (side_fractions, arc_fractions) = if ring_radius > 0. {
+                (
+                    fractions(side_count).collect::<Vec<f32>>(),
+                    arc_counts.map(|count| fractions(count).collect::<Vec<f32>>()),
+                )
+            } else {
+                (
+                    fractions(side_count).skip(1).collect(),
+                    [vec![0.], vec![0.]],
+                )
+            };
+            let offset = side.perp() * ring_radius;
+            let position = |piece: usize, t: f32| match piece {
+                0 => start + (end - start) * t - offset,
+                1 => end + rotate(-offset, PI * t),
+                2 => end + (start - end) * t + offset,
+                _ => start + rotate(offset, PI * t),
+            };
+            [
+                &side_fractions,
+                &arc_fractions[0],
+                &side_fractions,
+                &arc_fractions[1],
+            ]
+            .iter()
+            .enumerate()
+            .flat_map(|(piece, piece_fractions)| {
+                piece_fractions
+                    .iter()
+                    .map(move |&t| (piece as f32 + t, position(piece, t)))
+            })
+            .map(|(position_along, point)| (position_along, add_point(&mut points, point)))
+            .collect::<Vec<(f32, usize)>>()
+        })
+        .collect::<Vec<Vec<(f32, usize)>>>();
+
+    let triangles = ring_points
+        .windows(2)
+        .flat_map(|rings| stitch_rings(&rings[0], &rings[1], 4.))
+        .collect();
+    (points, triangles)
+}
+
+// Sides of the cells, given by their corners in order, along with whether they are on the
+// border of the mesh
+pub fn get_edges<T: AsRef<[usize]>>(cells: &[T]) -> Vec<((usize, usize), bool)> {
+    let mut sides = cells
+        .iter()
+        .flat_map(|cell| {
+            let corners = cell.as_ref();
+            corners
+                .iter()
+                .zip(corners.iter().cycle().skip(1))
+                .map(|(&start, &end)| (start, end))
+                .collect::<Vec<(usize, usize)>>()
+        })
+        .map(|(start, end)| (start.min(end), start.max(end)))
+        .collect::<Vec<(usize, usize)>>();
+    sides.sort_unstable();
+    let mut edges: Vec<((usize, usize), bool)> = vec![];
+    sides.into_iter().for_each(|side| match edges.last_mut() {
+        Some((last, is_on_border)) if *last == side => *is_on_border = false,
+        _ => edges.push((side, true)),
+    });
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Points spread without any pattern, the same on every run
+    fn scattered_points(count: usize, size: f32) -> Vec<Vec2> {
+        let mut seed = 12345u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 8) as f32 / (1 << 24) as f32
+        };
+        (0..count)
+            .map(|_| vec2(next() * size, next() * size))
+            .collect()
+    }
+
+    fn has_edge(triangles: &[[usize; 3]], start: usize, end: usize) -> bool {
+        get_edges(triangles)
+            .iter()
+            .any(|&(edge, _)| edge == (start.min(end), start.max(end)))
+    }
+
+    fn total_area(points: &[Vec2], triangles: &[[usize; 3]]) -> f32 {
+        triangles
+            .iter()
+            .map(|&triangle| triangle_area(points, triangle).abs())
+            .sum()
+    }
+
+    fn outline_area(outline: &[Vec2]) -> f32 {
+        outline
+            .iter()
+            .zip(outline.iter().cycle().skip(1))
+            .map(|(start, end)| start.perp_dot(*end))
+            .sum::<f32>()
+            .abs()
+            / 2.
+    }
+
+    // Every side of the outline is made of edges of the mesh through the points lying on it
+    fn assert_keeps_outline(outline: &[Vec2], points: &[Vec2], triangles: &[[usize; 3]]) {
+        outline
+            .iter()
+            .zip(outline.iter().cycle().skip(1))
+            .for_each(|(&start, &end)| {
+                let mut on_side = points
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, point)| distance_to_segment(**point, start, end) < 1e-3)
+                    .map(|(index, point)| ((*point - start).length(), index))
+                    .collect::<Vec<(f32, usize)>>();
+                on_side.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+                assert!(on_side.len() >= 2);
+                on_side.windows(2).for_each(|pair| {
+                    assert!(
+                        has_edge(triangles, pair[0].1, pair[1].1),
+                        "side from {} to {} is broken",
+                        start,
+                        end
+                    )
+                });
+            });
+    }
+
+    #[test]
+    fn triangulation_is_delaunay() {
+        let points = scattered_points(200, 500.);
+        let triangles = triangulate(&points);
+        assert!(!triangles.is_empty());
+        triangles.iter().for_each(|&triangle| {
+            let center = circumcenter(&points, triangle);
+            let radius = (points[triangle[0]] - center).length();
+            points.iter().enumerate().for_each(|(index, point)| {
+                if !triangle.contains(&index) {
+                    assert!((*point - center).length() > radius - 1e-2 * radius);
+                }
+            });
+        });
+    }
+
+    #[test]
+    fn triangulation_covers_the_points() {
+        let points = vec![
+            vec2(0., 0.),
+            vec2(100., 0.),
+            vec2(100., 100.),
+            vec2(0., 100.),
+        ];
+        let triangles = triangulate(&points);
+        assert_eq!(triangles.len(), 2);
+        assert!((total_area(&points, &triangles) - 10000.).abs() < 1e-2);
+    }
+
+    #[test]
+    fn constrained_triangulation_keeps_the_outline() {
+        // Concave, so the Delaunay triangulation of the corners alone crosses the outline
+        let outline = vec![
+            vec2(0., 0.),
+            vec2(300., 0.),
+            vec2(300., 200.),
+            vec2(150., 60.),
+            vec2(0., 200.),
+        ];
+        let quality = MeshQuality::default();
+        let (points, triangles) =
+            constrained_triangulation(&outline, &fill_outline(&outline, 40.), &quality);
+        assert_keeps_outline(&outline, &points, &triangles);
+        assert!((total_area(&points, &triangles) - outline_area(&outline)).abs() < 1.);
+    }
+
+    #[test]
+    fn constrained_triangulation_meets_the_quality() {
+        let outline = vec![
+            vec2(0., 0.),
+            vec2(400., 0.),
+            vec2(400., 100.),
+            vec2(0., 100.),
+        ];
+        let quality = MeshQuality {
+            min_angle: DEFAULT_MIN_ANGLE,
+            max_edge_length: Some(50.),
+        };
+        let (points, triangles) = constrained_triangulation(&outline, &[], &quality);
+        assert_keeps_outline(&outline, &points, &triangles);
+        triangles.iter().for_each(|&triangle| {
+            assert!(min_angle(&points, triangle) >= quality.min_angle - 1e-3);
+            assert!(max_edge_length(&points, triangle) <= 50. + 1e-3);
+        });
+    }
+
+    #[test]
+    fn constrained_triangulation_ends_on_sharp_outlines() {
+        let quality = MeshQuality::default();
+        let sliver = vec![vec2(0., 0.), vec2(300., 0.), vec2(300., 2.)];
+        // Sides shorter than twice the distance under which points are merged
+        let short_sides = vec![
+            vec2(0., 0.),
+            vec2(100., 0.),
+            vec2(100., 0.015),
+            vec2(100.015, 0.03),
+            vec2(0., 50.),
+        ];
+        // Drawn finely, the mouse giving a point every fraction of a pixel
+        let fine = (0..200)
+            .map(|index| {
+                let angle = 2. * PI * index as f32 / 200.;
+                vec2(angle.cos(), angle.sin()) * 30.
+            })
+            .collect::<Vec<Vec2>>();
+        [sliver, short_sides, fine].iter().for_each(|outline| {
+            let (points, triangles) =
+                constrained_triangulation(outline, &fill_outline(outline, 40.), &quality);
+            assert!(!triangles.is_empty());
+            assert!(points.len() <= outline.len() + MAX_REFINEMENT_POINTS);
+        });
+    }
+
+    #[test]
+    fn edges_on_the_border_belong_to_a_single_cell() {
+        let edges = get_edges(&[[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(
+            edges,
+            vec![
+                ((0, 1), true),
+                ((0, 2), false),
+                ((0, 3), true),
+                ((1, 2), true),
+                ((2, 3), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn radial_mesh_fills_a_ring() {
+        let (inner_radius, radius) = (20., 60.);
+        let (points, triangles) =
+            radial_mesh(vec2(0., 0.), vec2(0., 0.), inner_radius, radius, 24, 2);
+
+        // Stitched without overlaps, the triangles cover the band between the two border rings
+        let ring_area = |ring_radius: f32| {
+            let mut ring = points
+                .iter()
+                .filter(|point| (point.length() - ring_radius).abs() < 1e-3)
+                .copied()
+                .collect::<Vec<Vec2>>();
+            ring.sort_by(|a, b| a.y.atan2(a.x).partial_cmp(&b.y.atan2(b.x)).unwrap());
+            outline_area(&ring)
+        };
+        let area = ring_area(radius) - ring_area(inner_radius);
+        assert!((total_area(&points, &triangles) - area).abs() < 1e-1);
+
+        // Both the outer and the inner circles are the border
+        get_edges(&triangles)
+            .iter()
+            .for_each(|&((start, end), is_on_border)| {
+                let on_circle =
+                    |index: usize, circle: f32| (points[index].length() - circle).abs() < 1e-3;
+                let is_on_circle = [radius, inner_radius]
+                    .iter()
+                    .any(|&circle| on_circle(start, circle) && on_circle(end, circle));
+                assert_eq!(is_on_border, is_on_circle);
+            });
+        triangles
+            .iter()
+            .for_each(|&triangle| assert!(triangle_area(&points, triangle).abs() > MIN_AREA));
+    }
+
+    #[test]
+    fn radial_mesh_has_the_requested_border() {
+        (3..=16).for_each(|segments| {
+            // A capsule needs a dot to each side and two to each half circle
+            let lengths: &[f32] = if segments < 6 { &[0.] } else { &[0., 100.] };
+            lengths.iter().for_each(|&length| {
+                let (points, triangles) = radial_mesh(
+                    vec2(-length / 2., 0.),
+                    vec2(length / 2., 0.),
+                    0.,
+                    40.,
+                    segments,
+                    2,
+                );
+                let border = get_edges(&triangles)
+                    .into_iter()
+                    .filter(|(_, is_on_border)| *is_on_border)
+                    .map(|(edge, _)| edge)
+                    .collect::<Vec<(usize, usize)>>();
+                assert_eq!(border.len(), segments);
+
+                // The border being convex, its corners sorted around the center give its area
+                let mut corners = border
+                    .iter()
+                    .flat_map(|&(start, end)| [start, end])
+                    .collect::<Vec<usize>>();
+                corners.sort_unstable();
+                corners.dedup();
+                let mut outline = corners
+                    .iter()
+                    .map(|&index| points[index])
+                    .collect::<Vec<Vec2>>();
+                outline.sort_by(|a, b| a.y.atan2(a.x).partial_cmp(&b.y.atan2(b.x)).unwrap());
+                assert!((total_area(&points, &triangles) - outline_area(&outline)).abs() < 1e-1);
+            });
+        });
+    }
+}
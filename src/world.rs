@@ -0,0 +1,184 @@
+use crate::entities::drag::*;
+use crate::entities::interaction::*;
+use crate::entities::polygon::*;
+use crate::entities::soft_body::*;
+use crate::entities::xpbd::*;
+use crate::utils::integrator::*;
+use macroquad::prelude::{vec2, Vec2};
+
+#[derive(Copy, Clone, Debug)]
+pub enum Solver {
+    // Springs as forces, moved by the integrator
+    Forces,
+    // Springs and contacts as position constraints
+    Xpbd(XpbdSettings),
+}
+
+// Owns the whole simulation state, so it can be stepped without a window
+pub struct World {
+    pub bodies: Vec<SoftBody>,
+    pub polygons: Vec<Polygon>,
+    pub bounds: BoundingBox,
+    pub gravity: Vec2,
+    pub self_collision: bool,
+    pub integrator: Box<dyn Integrator>,
+    pub solver: Solver,
+    pub drag: Drag,
+}
+
+impl World {
+    pub fn new(bounds: BoundingBox) -> World {
+        World {
+            bodies: vec![],
+            polygons: vec![],
+            bounds,
+            gravity: vec2(0., GRAVITY),
+            self_collision: true,
+            integrator: Box::new(RungeKutta),
+            solver: Solver::Forces,
+            drag: Drag::new(None, None),
+        }
+    }
+
+    pub fn add_body(&mut self, body: SoftBody) -> usize {
+        self.bodies.push(body);
+        self.bodies.len() - 1
+    }
+
+    pub fn remove_body(&mut self, index: usize) -> SoftBody {
+        self.drag.forget_body(index);
+        self.bodies.remove(index)
+    }
+
+    // Index of the last added body containing the point
+    pub fn body_at(&self, point: Vec2) -> Option<usize> {
+        self.bodies
+            .iter()
+            .rposition(|body| body.is_point_inside(&point))
+    }
+
+    // Indices of the body and of its dot covering the point, looking at the last added bodies first
+    pub fn dot_at(&self, point: Vec2) -> Option<(usize, usize)> {
+        self.bodies
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, body)| body.dot_at(&point).map(|dot| (index, dot)))
+    }
+
+    // Grabs the closest dot of the body under the point, or a dot sticking out of a body
+    pub fn grab(&mut self, point: Vec2) -> bool {
+        let grabbed = self
+            .body_at(point)
+            .and_then(|body| self.bodies[body].closest_dot(&point).map(|dot| (body, dot)))
+            .or_else(|| self.dot_at(point));
+
+        if let Some((body, dot)) = grabbed {
+            self.drag.grab(body, dot);
+        }
+        grabbed.is_some()
+    }
+
+    pub fn release(&mut self) {
+        self.drag.release(&mut self.bodies);
+    }
+
+    pub fn add_polygon(&mut self, polygon: Polygon) -> usize {
+        self.polygons.push(polygon);
+        self.polygons.len() - 1
+    }
+
+    pub fn remove_polygon(&mut self, index: usize) -> Polygon {
+        self.polygons.remove(index)
+    }
+
+    pub fn get_energy(&self) -> f32 {
+        self.bodies
+            .iter()
+            .map(|body| body.get_energy(self.gravity))
+            .sum()
+    }
+
+    // Advances the world by the step, split in as many updates as the stiffest body needs for
+    // the integrator to stay stable when its springs are forces
+    pub fn step(&mut self, delta_t: f32) {
+        let substeps = match self.solver {
+            Solver::Forces => self
+                .bodies
+                .iter()
+                .map(|body| body.get_substeps(self.integrator.as_ref(), delta_t))
+                .max()
+                .unwrap_or(1),
+            Solver::Xpbd(_) => 1,
+        };
+        // Pinned dots head for their targets at a steady velocity over the whole step
+        self.bodies.iter_mut().for_each(|body| {
+            body.points
+                .iter_mut()
+                .for_each(|point| point.follow_pin(delta_t))
+        });
+        (0..substeps).for_each(|_| self.update(delta_t / substeps as f32));
+    }
+
+    fn update(&mut self, delta_t: f32) {
+        let World {
+            bodies,
+            polygons,
+            bounds,
+            gravity,
+            self_collision,
+            integrator,
+            solver,
+            drag,
+        } = self;
+
+        drag.apply(bodies);
+
+        bodies.iter_mut().for_each(|body| {
+            match solver {
+                Solver::Forces => {
+                    body.update(integrator.as_ref(), *gravity, delta_t);
+                    polygons
+                        .iter()
+                        .for_each(|polygon| body.handle_collision(polygon));
+                }
+                Solver::Xpbd(settings) => update_xpbd(body, polygons, settings, *gravity, delta_t),
+            }
+            body.deform(delta_t);
+        });
+
+        if *self_collision {
+            bodies.iter_mut().for_each(handle_self_collision);
+        }
+
+        (0..bodies.len()).for_each(|index| {
+            let (previous, next) = bodies.split_at_mut(index + 1);
+            next.iter_mut()
+                .for_each(|other| handle_body_body_collision(&mut previous[index], other));
+        });
+
+        // Torn bodies falling apart go on as separate ones
+        let mut pieces = vec![];
+        bodies.iter_mut().enumerate().for_each(|(index, body)| {
+            if body.tear() {
+                if let Some(body_pieces) = body.split() {
+                    drag.let_go(index);
+                    pieces.extend(body_pieces);
+                }
+            }
+        });
+        bodies.extend(pieces);
+
+        // Bodies that left the world or crumbled to dust are not coming back
+        let lost_bodies = (0..bodies.len())
+            .rev()
+            .filter(|&index| {
+                bodies[index].is_out_of_bounds(bounds) || bodies[index].springs.is_empty()
+            })
+            .collect::<Vec<usize>>();
+        lost_bodies.into_iter().for_each(|index| {
+            drag.forget_body(index);
+            bodies.remove(index);
+        });
+    }
+}
@@ -0,0 +1,30 @@
+use macroquad::prelude::vec2;
+use soft_body_simulation::entities::dot::{Dot, MIN_MASS, RADIUS};
+use soft_body_simulation::entities::interaction::handle_point_point_collision;
+
+#[test]
+fn mass_is_kept_positive() {
+    let mut dot = Dot::new(vec2(0., 0.));
+    [0., -1., f32::NAN].into_iter().for_each(|mass| {
+        dot.set_mass(mass);
+        assert_eq!(dot.mass(), MIN_MASS);
+        assert!(!dot.is_frozen());
+    });
+}
+
+#[test]
+fn infinite_mass_freezes_the_dot() {
+    let mut dot = Dot::new(vec2(0., 0.));
+    dot.set_mass(f32::INFINITY);
+    assert!(dot.is_frozen());
+}
+
+#[test]
+fn overlapping_dots_separate_without_speeding_apart() {
+    let mut points = [Dot::new(vec2(0., 0.)), Dot::new(vec2(4., 0.))];
+    points[0].add_velocity(&vec2(1., 0.));
+    points[1].add_velocity(&vec2(-1., 0.));
+    handle_point_point_collision(&mut points, 0, 1);
+    assert!(((points[1].pos - points[0].pos).length() - RADIUS).abs() < 1e-4);
+    assert!(points[0].vel.length() < 1e-4 && points[1].vel.length() < 1e-4);
+}
@@ -0,0 +1,40 @@
+use macroquad::prelude::vec2;
+use soft_body_simulation::entities::material::Material;
+use soft_body_simulation::entities::polygon::BoundingBox;
+use soft_body_simulation::entities::soft_body::SoftBody;
+use soft_body_simulation::utils::integrator::integrators;
+use soft_body_simulation::world::World;
+
+#[test]
+fn energy_stays_bounded_without_damping() {
+    integrators().into_iter().for_each(|integrator| {
+        let name = integrator.name();
+        let mut world = World::new(BoundingBox::new(&[(-4000., -4000.), (4000., 4000.)]));
+        world.gravity = vec2(0., 0.);
+        world.integrator = integrator;
+        let material = Material {
+            damping: 0.,
+            ..Material::default()
+        };
+        // Spinning and swelling, so that every spring keeps oscillating
+        let mut body = SoftBody::new(0., 0., None, None, None, Some(material));
+        body.points.iter_mut().for_each(|point| {
+            let pos = point.pos;
+            point.add_velocity(&((pos.perp() + pos) * 0.05));
+        });
+        world.add_body(body);
+
+        let initial = world.get_energy();
+        (0..4000).for_each(|_| {
+            world.step(0.25);
+            let energy = world.get_energy();
+            assert!(
+                energy < 1.5 * initial && energy > 0.25 * initial,
+                "{} drifted from {} to {}",
+                name,
+                initial,
+                energy
+            );
+        });
+    });
+}
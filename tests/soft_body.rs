@@ -0,0 +1,350 @@
+use macroquad::prelude::vec2;
+use soft_body_simulation::entities::dot::RADIUS;
+use soft_body_simulation::entities::lattice::{Lattice, Resolution, MIN_CELL_SIZE};
+use soft_body_simulation::entities::material::{
+    Material, DEFAULT_BULK_MODULUS, DEFAULT_SHAPE_STIFFNESS, MAX_SPEED,
+};
+use soft_body_simulation::entities::polygon::{BoundingBox, Polygon};
+use soft_body_simulation::entities::soft_body::{SoftBody, SpringKind};
+use soft_body_simulation::utils::mesh::DEFAULT_SPACING;
+use soft_body_simulation::world::World;
+use std::f32::consts::PI;
+
+fn active_springs(body: &SoftBody, kind: SpringKind) -> usize {
+    body.springs
+        .iter()
+        .filter(|spring| spring.kind == kind && spring.is_active())
+        .count()
+}
+
+#[test]
+fn material_without_shear_keeps_the_springs() {
+    let mut body = SoftBody::new(0., 0., None, None, None, Some(Material::rubber()));
+    let springs = body.springs.len();
+    let shear_springs = active_springs(&body, SpringKind::Shear);
+    assert!(shear_springs > 0);
+
+    body.set_material(Material {
+        shear_stiffness: None,
+        ..Material::rubber()
+    });
+    assert_eq!(body.springs.len(), springs);
+    assert_eq!(active_springs(&body, SpringKind::Shear), 0);
+
+    body.set_material(Material::rubber());
+    assert_eq!(active_springs(&body, SpringKind::Shear), shear_springs);
+}
+
+#[test]
+fn square_cells_are_two_triangles() {
+    let body = SoftBody::new(0., 0., Some((160., 120.)), None, None, None);
+    // 4 by 3 cells of the default size
+    assert_eq!(body.triangles.len(), 2 * 4 * 3);
+}
+
+#[test]
+fn finer_bodies_weigh_the_same() {
+    let mass = |resolution| {
+        SoftBody::new(0., 0., Some((160., 120.)), Some(resolution), None, None)
+            .points
+            .iter()
+            .map(|point| point.mass())
+            .sum::<f32>()
+    };
+    let (coarse, fine) = (
+        mass(Resolution::CellSize(40.)),
+        mass(Resolution::CellSize(10.)),
+    );
+    // The mass of the material for each square of the default spacing
+    let expected = 160. * 120. / DEFAULT_SPACING.powi(2) * Material::default().mass;
+    assert!((coarse - expected).abs() < 0.01 * expected);
+    assert!((fine - expected).abs() < 0.01 * expected);
+}
+
+#[test]
+fn round_bodies_weigh_by_their_area() {
+    let circle = SoftBody::circle(vec2(0., 0.), 80., None, None, None)
+        .points
+        .iter()
+        .map(|point| point.mass())
+        .sum::<f32>();
+    let expected = PI * 80f32.powi(2) / DEFAULT_SPACING.powi(2) * Material::default().mass;
+    // The rings of dots cut the corners of the circle
+    assert!((circle - expected).abs() < 0.05 * expected);
+}
+
+#[test]
+fn smallest_circle_is_a_triangle() {
+    let circle = SoftBody::circle(vec2(0., 0.), 40., Some(3), None, None);
+    assert_eq!(circle.border_springs().len(), 3);
+}
+
+#[test]
+fn finest_bodies_rest_on_the_floor() {
+    [Lattice::Square, Lattice::Triangular, Lattice::Hexagonal]
+        .into_iter()
+        .for_each(|lattice| {
+            let mut world = World::new(BoundingBox::new(&[(-2000., -2000.), (2000., 2000.)]));
+            world.add_polygon(Polygon::generate_floor(800., 600.));
+            world.add_body(SoftBody::new(
+                400.,
+                300.,
+                None,
+                Some(Resolution::CellSize(MIN_CELL_SIZE)),
+                Some(lattice),
+                None,
+            ));
+            (0..2000).for_each(|_| world.step(0.25));
+            assert_eq!(world.bodies.len(), 1, "{:?} body was lost", lattice);
+            let bounds = world.bodies[0].bounding_box();
+            assert!(bounds.max_ver < 560. && bounds.min_hor > 0. && bounds.max_hor < 800.);
+            let speed = world.bodies[0]
+                .points
+                .iter()
+                .map(|point| point.vel.length())
+                .fold(0., f32::max);
+            assert!(speed < 2., "{:?} body still moves at {}", lattice, speed);
+        });
+}
+
+#[test]
+fn lattices_stand_without_shear_and_bend_springs() {
+    let material = Material {
+        shear_stiffness: None,
+        bend_stiffness: None,
+        ..Material::rubber()
+    };
+    [Lattice::Square, Lattice::Triangular, Lattice::Hexagonal]
+        .into_iter()
+        .for_each(|lattice| {
+            let mut world = World::new(BoundingBox::new(&[(-2000., -2000.), (2000., 2000.)]));
+            world.add_polygon(Polygon::generate_floor(800., 600.));
+            // Standing on the floor rather than falling on it, which folds the cells
+            world.add_body(SoftBody::new(
+                400.,
+                465.,
+                None,
+                None,
+                Some(lattice),
+                Some(material),
+            ));
+            let height = |world: &World| {
+                let bounds = world.bodies[0].bounding_box();
+                bounds.max_ver - bounds.min_ver
+            };
+            let rest_height = height(&world);
+            (0..3000).for_each(|_| world.step(0.25));
+            assert!(
+                height(&world) > rest_height * 0.7,
+                "{:?} body collapsed",
+                lattice
+            );
+        });
+}
+
+#[test]
+fn cloth_hangs_from_its_whole_top_row() {
+    let cloth = SoftBody::cloth(
+        13.7,
+        -41.3,
+        Some((173.3, 97.1)),
+        Some(Resolution::Cells(7, 5)),
+        true,
+        None,
+    );
+    let top = cloth
+        .points
+        .iter()
+        .map(|point| point.pos.y)
+        .fold(f32::INFINITY, f32::min);
+    let pinned = cloth
+        .points
+        .iter()
+        .filter(|point| point.is_pinned())
+        .collect::<Vec<_>>();
+    // Every dot of the 7 columns of cells
+    assert_eq!(pinned.len(), 8);
+    assert!(pinned.iter().all(|point| (point.pos.y - top).abs() < 1e-3));
+}
+
+#[test]
+fn balloon_inflates_until_its_springs_hold_the_gas() {
+    let mut world = World::new(BoundingBox::new(&[(-2000., -2000.), (2000., 2000.)]));
+    world.gravity = vec2(0., 0.);
+    world.add_body(SoftBody::new_balloon(0., 0., 80., None, None, None));
+    let balloon = &world.bodies[0];
+    let sides = balloon.points.len() as f32;
+    let rest_side = (balloon.points[1].pos - balloon.points[0].pos).length();
+    let amount = balloon.pressure.as_ref().unwrap().amount;
+    (0..3000).for_each(|_| world.step(0.25));
+
+    // Each corner is pushed out by the gas on half of its two sides and pulled in by its two
+    // springs, which on a regular polygon balance when k·s·(s - s0) = 2·P·A / n
+    let stiffness = Material::default().border_stiffness;
+    let side = (rest_side + (rest_side.powi(2) + 8. * amount / (sides * stiffness)).sqrt()) / 2.;
+    let expected = sides * side.powi(2) / (4. * (PI / sides).tan());
+    let balloon = &world.bodies[0];
+    let area = balloon.pressure.as_ref().unwrap().get_area(
+        &balloon
+            .points
+            .iter()
+            .map(|point| point.pos)
+            .collect::<Vec<_>>(),
+    );
+    assert!(
+        (area - expected).abs() < 0.01 * expected,
+        "balloon of area {} instead of {}",
+        area,
+        expected
+    );
+}
+
+fn cell_areas(body: &SoftBody) -> Vec<f32> {
+    let positions = body
+        .points
+        .iter()
+        .map(|point| point.pos)
+        .collect::<Vec<_>>();
+    body.triangles
+        .iter()
+        .map(|triangle| triangle.get_area(&positions))
+        .collect()
+}
+
+#[test]
+fn shape_matching_turns_a_mirrored_body_back() {
+    // Springs softer than the pull towards the shape, as they cannot tell the body from its
+    // mirror image
+    let springs = Material::new(1., 0.8, 1., 1., RADIUS, MAX_SPEED);
+    [None, Some(DEFAULT_SHAPE_STIFFNESS)]
+        .into_iter()
+        .for_each(|shape_matching| {
+            let mut world = World::new(BoundingBox::new(&[(-2000., -2000.), (2000., 2000.)]));
+            world.gravity = vec2(0., 0.);
+            // The border has to go through itself for the body to turn back
+            world.self_collision = false;
+            let material = Material {
+                shape_matching,
+                ..springs
+            };
+            world.add_body(SoftBody::new(0., 0., None, None, None, Some(material)));
+            let rest_areas = cell_areas(&world.bodies[0]);
+            world.bodies[0]
+                .points
+                .iter_mut()
+                .for_each(|point| point.pos.x = -point.pos.x);
+            (0..1000).for_each(|_| world.step(0.25));
+
+            cell_areas(&world.bodies[0])
+                .iter()
+                .zip(rest_areas)
+                .for_each(|(area, rest_area)| match shape_matching {
+                    Some(_) => assert!(
+                        (area - rest_area).abs() < 0.01 * rest_area,
+                        "cell of area {} instead of {}",
+                        area,
+                        rest_area
+                    ),
+                    None => assert!(*area < 0., "springs turned a cell back"),
+                });
+        });
+}
+
+#[test]
+fn crushed_cells_grow_back_to_their_area() {
+    // Springs without stiffness, so that only the cells push back
+    let springs = Material::new(0., 0.8, 0., 1., RADIUS, MAX_SPEED);
+    [None, Some(DEFAULT_BULK_MODULUS)]
+        .into_iter()
+        .for_each(|bulk_modulus| {
+            let mut world = World::new(BoundingBox::new(&[(-2000., -2000.), (2000., 2000.)]));
+            world.gravity = vec2(0., 0.);
+            let material = Material {
+                bulk_modulus,
+                ..springs
+            };
+            world.add_body(SoftBody::new(0., 0., None, None, None, Some(material)));
+            let rest_areas = cell_areas(&world.bodies[0]);
+            world.bodies[0]
+                .points
+                .iter_mut()
+                .for_each(|point| point.pos.y /= 2.);
+            (0..1000).for_each(|_| world.step(0.25));
+
+            cell_areas(&world.bodies[0])
+                .iter()
+                .zip(rest_areas)
+                .for_each(|(area, rest_area)| match bulk_modulus {
+                    Some(_) => assert!(
+                        (area - rest_area).abs() < 0.01 * rest_area,
+                        "cell of area {} instead of {}",
+                        area,
+                        rest_area
+                    ),
+                    None => assert!(
+                        *area < 0.6 * rest_area,
+                        "cell grew back without a bulk modulus"
+                    ),
+                });
+        });
+}
+
+#[test]
+fn stretched_clay_keeps_part_of_its_length() {
+    [Material::rubber(), Material::clay()]
+        .into_iter()
+        .for_each(|material| {
+            let mut world = World::new(BoundingBox::new(&[(-2000., -2000.), (2000., 2000.)]));
+            world.gravity = vec2(0., 0.);
+            world.add_body(SoftBody::new(0., 0., None, None, None, Some(material)));
+            let width = |body: &SoftBody| {
+                let bounds = body.bounding_box();
+                bounds.max_hor - bounds.min_hor
+            };
+            let rest_width = width(&world.bodies[0]);
+            // Held half as long again by its sides
+            world.bodies[0].points.iter_mut().for_each(|point| {
+                let is_on_side = point.pos.x.abs() == rest_width / 2.;
+                point.pos.x *= 1.5;
+                if is_on_side {
+                    point.pin(Some(point.pos));
+                }
+            });
+            (0..400).for_each(|_| world.step(0.25));
+
+            let body = &world.bodies[0];
+            if let Some(plasticity) = material.plasticity {
+                // The rest length of the springs along the stretch creeps until they only
+                // strain by the yield ratio
+                body.springs
+                    .iter()
+                    .filter(|spring| {
+                        let (start, end) =
+                            (body.points[spring.index_1], body.points[spring.index_2]);
+                        spring.kind == SpringKind::Structural && start.rest_pos.y == end.rest_pos.y
+                    })
+                    .for_each(|spring| {
+                        let strain = spring.get_strain(
+                            body.points[spring.index_1].pos,
+                            body.points[spring.index_2].pos,
+                        );
+                        assert!(
+                            (strain - plasticity.yield_ratio).abs() < 0.01,
+                            "spring strained by {}",
+                            strain
+                        );
+                    });
+            }
+
+            world.bodies[0]
+                .points
+                .iter_mut()
+                .for_each(|point| point.unpin());
+            (0..1000).for_each(|_| world.step(0.25));
+            let ratio = width(&world.bodies[0]) / rest_width;
+            match material.plasticity {
+                Some(_) => assert!(ratio > 1.3, "clay shrank back to {} its width", ratio),
+                None => assert!((ratio - 1.).abs() < 0.01, "rubber kept {} its width", ratio),
+            }
+        });
+}
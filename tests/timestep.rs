@@ -0,0 +1,30 @@
+use soft_body_simulation::entities::polygon::BoundingBox;
+use soft_body_simulation::timestep::FixedTimestep;
+use soft_body_simulation::world::World;
+
+fn new_world() -> World {
+    World::new(BoundingBox::new(&[(-100., -100.), (100., 100.)]))
+}
+
+#[test]
+fn steps_at_a_fixed_rate() {
+    let mut world = new_world();
+    let mut timestep = FixedTimestep::new(0.25, Some(60.), None, Some(5));
+    assert_eq!(timestep.advance(&mut world, 0.5 / 60.), 0);
+    assert_eq!(timestep.advance(&mut world, 0.5 / 60.), 1);
+    assert_eq!(timestep.advance(&mut world, 2.5 / 60.), 2);
+}
+
+#[test]
+fn drops_the_rest_of_a_long_frame() {
+    let mut world = new_world();
+    let mut timestep = FixedTimestep::new(0.25, Some(60.), None, Some(5));
+    assert_eq!(timestep.advance(&mut world, 1.), 5);
+    assert_eq!(timestep.advance(&mut world, 0.), 0);
+}
+
+#[test]
+#[should_panic]
+fn rejects_no_steps_per_frame() {
+    FixedTimestep::new(0.25, None, None, Some(0));
+}
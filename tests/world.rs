@@ -0,0 +1,287 @@
+use macroquad::prelude::{vec2, Vec2};
+use soft_body_simulation::entities::polygon::{BoundingBox, Polygon};
+use soft_body_simulation::entities::soft_body::SoftBody;
+use soft_body_simulation::entities::xpbd::XpbdSettings;
+use soft_body_simulation::world::{Solver, World};
+
+const WIDTH: f32 = 800.;
+const HEIGHT: f32 = 600.;
+// Top of the floor generated for the size of the world
+const FLOOR: f32 = HEIGHT - 50.;
+
+fn new_world(solver: Solver, with_floor: bool) -> World {
+    let mut world = World::new(BoundingBox::new(&[
+        (-WIDTH * 2., -HEIGHT * 2.),
+        (WIDTH * 2., HEIGHT * 2.),
+    ]));
+    world.solver = solver;
+    if with_floor {
+        world.add_polygon(Polygon::generate_floor(WIDTH, HEIGHT));
+    }
+    world.add_body(SoftBody::new(
+        WIDTH / 2.,
+        HEIGHT / 3.,
+        None,
+        None,
+        None,
+        None,
+    ));
+    world
+}
+
+fn center(body: &SoftBody) -> Vec2 {
+    body.points
+        .iter()
+        .fold(vec2(0., 0.), |sum, point| sum + point.pos)
+        / body.points.len() as f32
+}
+
+fn step(world: &mut World, steps: usize) {
+    (0..steps).for_each(|_| world.step(0.25));
+}
+
+fn positions(world: &World) -> Vec<Vec2> {
+    world
+        .bodies
+        .iter()
+        .flat_map(|body| body.points.iter().map(|point| point.pos))
+        .collect()
+}
+
+// Well below the speed a step of gravity adds, which a bouncing dot keeps
+fn assert_settled_on_floor(world: &mut World) {
+    assert_eq!(world.bodies.len(), 1);
+    let body = &world.bodies[0];
+    body.points.iter().for_each(|point| {
+        assert!(point.pos.is_finite());
+        assert!(
+            point.pos.y < FLOOR,
+            "dot sunk into the floor at {}",
+            point.pos
+        );
+        assert!(
+            point.vel.length() < 0.1,
+            "dot still moving at {}",
+            point.vel
+        );
+    });
+    assert!(center(body).y > FLOOR - 100.);
+    let before = positions(world);
+    step(world, 50);
+    positions(world)
+        .iter()
+        .zip(before)
+        .for_each(|(after, before)| {
+            assert!(
+                (*after - before).length() < 0.05,
+                "dot moved from {} to {}",
+                before,
+                after
+            )
+        });
+}
+
+#[test]
+fn body_falls_without_window() {
+    let mut world = new_world(Solver::Forces, false);
+    let start = center(&world.bodies[0]);
+    step(&mut world, 100);
+    let end = center(&world.bodies[0]);
+    assert!(end.y > start.y + 10.);
+    assert!((end.x - start.x).abs() < 1.);
+}
+
+#[test]
+fn body_settles_on_floor_with_forces() {
+    let mut world = new_world(Solver::Forces, true);
+    step(&mut world, 3000);
+    assert_settled_on_floor(&mut world);
+}
+
+#[test]
+fn body_settles_on_floor_with_xpbd() {
+    let mut world = new_world(Solver::Xpbd(XpbdSettings::default()), true);
+    step(&mut world, 3000);
+    assert_settled_on_floor(&mut world);
+}
+
+#[test]
+fn body_leaving_the_bounds_is_removed() {
+    let mut world = new_world(Solver::Forces, false);
+    step(&mut world, 3000);
+    assert!(world.bodies.is_empty());
+}
+
+#[test]
+fn outline_on_a_line_makes_no_body() {
+    let outline = [vec2(100., 100.), vec2(200., 100.), vec2(300., 100.)];
+    assert!(SoftBody::from_outline(&outline, None, None).is_none());
+}
+
+#[test]
+fn body_without_dots_does_not_stop_the_world() {
+    let mut world = new_world(Solver::Forces, true);
+    let mut empty = SoftBody::new(WIDTH / 2., HEIGHT / 2., None, None, None, None);
+    empty.points.clear();
+    empty.springs.clear();
+    empty.triangles.clear();
+    world.add_body(empty);
+    world.step(0.25);
+    assert_eq!(world.bodies.len(), 1);
+}
+
+fn stacked_bodies_come_to_rest(solver: Solver) {
+    let mut world = new_world(solver, true);
+    // Laid on top of each other rather than dropped, the test being about resting
+    world.bodies.clear();
+    [FLOOR - 70., FLOOR - 200.].into_iter().for_each(|height| {
+        world.add_body(SoftBody::new(WIDTH / 2., height, None, None, None, None));
+    });
+    step(&mut world, 2000);
+    let before = positions(&world);
+    step(&mut world, 500);
+    assert_eq!(world.bodies.len(), 2);
+    assert!(center(&world.bodies[1]).y < center(&world.bodies[0]).y - 100.);
+    positions(&world)
+        .iter()
+        .zip(before)
+        .for_each(|(after, before)| {
+            assert!(
+                (*after - before).length() < 0.5,
+                "dot moved from {} to {}",
+                before,
+                after
+            )
+        });
+}
+
+#[test]
+fn stacked_bodies_come_to_rest_with_forces() {
+    stacked_bodies_come_to_rest(Solver::Forces);
+}
+
+#[test]
+fn stacked_bodies_come_to_rest_with_xpbd() {
+    stacked_bodies_come_to_rest(Solver::Xpbd(XpbdSettings::default()));
+}
+
+fn moving_pin_lands_on_its_target(solver: Solver) {
+    let mut world = new_world(solver, false);
+    world.gravity = vec2(0., 0.);
+    // Held by two corners, the body can only follow them without turning
+    let corners = [0, world.bodies[0].points.len() - 1];
+    let starts = corners.map(|corner| world.bodies[0].points[corner].pos);
+    let offset = center(&world.bodies[0]) - starts[0];
+    (1..=200).for_each(|step| {
+        let targets = starts.map(|start| start + vec2(2., 0.) * step as f32);
+        corners.iter().zip(targets).for_each(|(&corner, target)| {
+            world.bodies[0].points[corner].pin(Some(target));
+        });
+        world.step(0.25);
+        corners.iter().zip(targets).for_each(|(&corner, target)| {
+            let point = &world.bodies[0].points[corner];
+            assert!((point.pos - target).length() < 1e-3);
+            assert!((point.vel - vec2(8., 0.)).length() < 1e-3);
+        });
+    });
+    // Nothing pulls the body ahead of the dots it hangs from
+    let offset_change = center(&world.bodies[0]) - world.bodies[0].points[0].pos - offset;
+    assert!(
+        offset_change.length() < 0.1,
+        "body moved by {}",
+        offset_change
+    );
+}
+
+#[test]
+fn moving_pin_lands_on_its_target_with_forces() {
+    moving_pin_lands_on_its_target(Solver::Forces);
+}
+
+#[test]
+fn moving_pin_lands_on_its_target_with_xpbd() {
+    moving_pin_lands_on_its_target(Solver::Xpbd(XpbdSettings::default()));
+}
+
+fn released_body_keeps_the_drag_velocity(solver: Solver) {
+    let mut world = new_world(solver, false);
+    world.gravity = vec2(0., 0.);
+    assert!(world.grab(center(&world.bodies[0])));
+    let (_, dot) = world.drag.grabbed().unwrap();
+    let target = world.bodies[0].points[dot].pos;
+    // Twice, for the mouse to be at rest on the dot
+    (0..2).for_each(|_| world.drag.move_to(target, 0.25));
+    step(&mut world, 20);
+    // Flicked from rest, the mouse moving at 40 by 20 when letting go
+    world.drag.move_to(target + vec2(10., -5.), 0.25);
+    world.release();
+    let start = center(&world.bodies[0]);
+    step(&mut world, 10);
+    let travel = center(&world.bodies[0]) - start;
+    assert!(
+        (travel - vec2(100., -50.)).length() < 0.1,
+        "body moved by {}",
+        travel
+    );
+}
+
+#[test]
+fn released_body_keeps_the_drag_velocity_with_forces() {
+    released_body_keeps_the_drag_velocity(Solver::Forces);
+}
+
+#[test]
+fn released_body_keeps_the_drag_velocity_with_xpbd() {
+    released_body_keeps_the_drag_velocity(Solver::Xpbd(XpbdSettings::default()));
+}
+
+// Border dots of the body lying inside one of its cells they are not a corner of
+fn tangled_dots(body: &SoftBody) -> usize {
+    let positions = body
+        .points
+        .iter()
+        .map(|point| point.pos)
+        .collect::<Vec<Vec2>>();
+    let mut border_dots = body
+        .border_springs()
+        .iter()
+        .flat_map(|spring| [spring.index_1, spring.index_2])
+        .collect::<Vec<usize>>();
+    border_dots.sort_unstable();
+    border_dots.dedup();
+    border_dots
+        .into_iter()
+        .filter(|index| {
+            body.triangles.iter().any(|triangle| {
+                let [a, b, c] = triangle.indices.map(|corner| positions[corner]);
+                let sides = [(a, b), (b, c), (c, a)]
+                    .map(|(start, end)| (end - start).perp_dot(positions[*index] - start));
+                !triangle.indices.contains(index)
+                    && (sides.iter().all(|side| *side > 0.) || sides.iter().all(|side| *side < 0.))
+            })
+        })
+        .count()
+}
+
+#[test]
+fn self_collision_keeps_a_squeezed_ring_apart() {
+    [false, true].into_iter().for_each(|self_collision| {
+        let mut world = new_world(Solver::Forces, false);
+        world.gravity = vec2(0., 0.);
+        world.self_collision = self_collision;
+        world.bodies.clear();
+        let center = vec2(WIDTH / 2., HEIGHT / 3.);
+        world.add_body(SoftBody::ring(center, 100., 60., None, None, None));
+        // Both halves thrown at each other, hard enough for the inside to meet
+        world.bodies[0].points.iter_mut().for_each(|point| {
+            point.vel = vec2(0., 60.) * (center.y - point.pos.y).signum();
+        });
+        step(&mut world, 400);
+        let tangled = tangled_dots(&world.bodies[0]);
+        if self_collision {
+            assert_eq!(tangled, 0, "{} dots went through the ring", tangled);
+        } else {
+            assert!(tangled > 0, "the ring held without self-collision");
+        }
+    });
+}